@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as StdMutex};
+
+use acog::COG;
+use tokio::sync::{Mutex, OnceCell};
+
+/// A process-wide cache of opened `COG`s, keyed by the source spec (filename/`/vsis3/...`/...)
+/// they were opened from.
+///
+/// Opening a COG re-reads its header/IFDs and throws away its `Source` (and the `ChunkCache`
+/// backing it) as soon as the request is done. For a tile server, that means N concurrent
+/// requests for the same file each pay for their own header read and their own S3/GCS range
+/// requests, even though the result would be identical.
+///
+/// `CogCache` keeps one `COG` alive per source spec behind an `Arc<Mutex<..>>` so subsequent
+/// requests reuse it, and uses `OnceCell` to single-flight concurrent opens of the same spec: the
+/// first request to miss does the actual `COG::open`, late arrivals just await that same future
+/// instead of starting their own.
+#[derive(Clone, Default)]
+pub struct CogCache {
+    entries: Arc<StdMutex<HashMap<String, Arc<OnceCell<Arc<Mutex<COG>>>>>>>,
+}
+
+impl CogCache {
+    pub fn new() -> CogCache {
+        CogCache::default()
+    }
+
+    /// Returns the `COG` opened from `source_spec`, opening (and caching) it if this is the
+    /// first request for it. Concurrent calls with the same `source_spec` share a single
+    /// in-flight open.
+    pub async fn get_or_open(&self, source_spec: &str) -> Result<Arc<Mutex<COG>>, acog::Error> {
+        let cell = {
+            let mut entries = self.entries.lock().unwrap();
+            entries
+                .entry(source_spec.to_string())
+                .or_insert_with(|| Arc::new(OnceCell::new()))
+                .clone()
+        };
+        let cog = cell
+            .get_or_try_init(|| async {
+                let cog = COG::open(source_spec).await?;
+                Ok::<_, acog::Error>(Arc::new(Mutex::new(cog)))
+            })
+            .await?;
+        Ok(cog.clone())
+    }
+}
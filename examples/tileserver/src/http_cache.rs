@@ -0,0 +1,119 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::header::HeaderMap;
+use hyper::{Response, StatusCode};
+
+use crate::{Error, HandlerResponse};
+
+/// Computes a stable, quoted ETag from a set of parts that uniquely identify a response body
+/// (e.g. the source spec + z/x/y of a tile). This is a weak proxy for hashing the actual tile
+/// bytes (offset/bytecount in the COG would be the precise version), but is cheap to compute and
+/// stable across requests for the same tile.
+pub fn compute_etag(parts: &[&str]) -> String {
+    let mut hasher = DefaultHasher::new();
+    for part in parts {
+        part.hash(&mut hasher);
+    }
+    format!("\"{:x}\"", hasher.finish())
+}
+
+/// We don't track per-file modification times (files may live on S3/GCS), so we use the time the
+/// server started as a stable `Last-Modified`: it never goes backwards and content served before
+/// a restart is never newer than it.
+fn server_start_http_date() -> &'static str {
+    static START: OnceLock<String> = OnceLock::new();
+    START.get_or_init(|| httpdate::fmt_http_date(SystemTime::now()))
+}
+
+/// Returns true if `If-None-Match` is present and matches `etag` (or is `*`), meaning the client's
+/// cached copy is still valid and a `304 Not Modified` should be returned instead of the full body.
+pub fn not_modified(headers: &HeaderMap, etag: &str) -> bool {
+    match headers.get(hyper::header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        Some(value) => value.split(',').map(str::trim).any(|v| v == "*" || v == etag),
+        None => false,
+    }
+}
+
+pub fn not_modified_response() -> Result<HandlerResponse, Error> {
+    Ok(Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .body(Full::from(Bytes::new()))?)
+}
+
+/// A half-open byte range `[start, end]` (inclusive), already clamped to `total_len`.
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Parses a single-range `Range: bytes=start-end` header. Multi-range requests and anything we
+/// can't make sense of are ignored (we just fall back to serving the full body), matching what
+/// most static file servers do for unsupported range syntax.
+pub fn parse_range(headers: &HeaderMap, total_len: u64) -> Option<ByteRange> {
+    let value = headers.get(hyper::header::RANGE)?.to_str().ok()?;
+    let spec = value.strip_prefix("bytes=")?;
+    // Reject multi-range requests (containing a comma) - we only support a single range
+    if spec.contains(',') {
+        return None;
+    }
+    let (start_str, end_str) = spec.split_once('-')?;
+    let (start, end) = if start_str.is_empty() {
+        // "bytes=-N" means the last N bytes
+        let suffix_len: u64 = end_str.parse().ok()?;
+        let start = total_len.saturating_sub(suffix_len);
+        (start, total_len.saturating_sub(1))
+    } else {
+        let start: u64 = start_str.parse().ok()?;
+        let end: u64 = if end_str.is_empty() {
+            total_len.saturating_sub(1)
+        } else {
+            end_str.parse().ok()?
+        };
+        (start, end)
+    };
+    if start > end || start >= total_len {
+        return None;
+    }
+    Some(ByteRange {
+        start,
+        end: end.min(total_len.saturating_sub(1)),
+    })
+}
+
+/// Applies the standard caching headers (`ETag`, `Last-Modified`, `Accept-Ranges`, `Cache-Control`)
+/// and, if the request carries a satisfiable `Range` header, serves a `206 Partial Content` slice
+/// of `body` instead of the full thing.
+pub fn respond_with_caching(
+    headers: &HeaderMap,
+    etag: &str,
+    content_type: &str,
+    body: Vec<u8>,
+) -> Result<HandlerResponse, Error> {
+    if not_modified(headers, etag) {
+        return not_modified_response();
+    }
+    let total_len = body.len() as u64;
+    let builder = Response::builder()
+        .header("ETag", etag)
+        .header("Last-Modified", server_start_http_date())
+        .header("Accept-Ranges", "bytes")
+        .header("Cache-Control", "public, max-age=3600")
+        .header("Content-Type", content_type);
+    if let Some(range) = parse_range(headers, total_len) {
+        let chunk = body[range.start as usize..=range.end as usize].to_vec();
+        Ok(builder
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(
+                "Content-Range",
+                format!("bytes {}-{}/{}", range.start, range.end, total_len),
+            )
+            .body(chunk.into())?)
+    } else {
+        Ok(builder.status(StatusCode::OK).body(body.into())?)
+    }
+}
@@ -14,6 +14,12 @@ use std::pin::Pin;
 
 use hyper_util::rt::tokio::{TokioIo, TokioTimer};
 
+mod cache;
+mod http_cache;
+mod tile_encode;
+use cache::CogCache;
+use hyper::header::HeaderMap;
+
 const INDEX: &str = include_str!("index.html");
 
 enum Error {
@@ -69,11 +75,19 @@ async fn index() -> Result<HandlerResponse, Error> {
     Ok(builder.body(INDEX.to_string().into_bytes().into())?)
 }
 
-async fn get_bounds(filename: &str) -> Result<HandlerResponse, Error> {
+async fn get_bounds(
+    cache: &CogCache,
+    headers: &HeaderMap,
+    filename: &str,
+) -> Result<HandlerResponse, Error> {
     check_filename(filename)?;
     println!("get_bounds {}", filename);
-    let cog = acog::COG::open(filename).await?;
-    let bbox = cog.lnglat_bounds()?;
+    let etag = http_cache::compute_etag(&["bounds", filename]);
+    if http_cache::not_modified(headers, &etag) {
+        return http_cache::not_modified_response();
+    }
+    let cog = cache.get_or_open(filename).await?;
+    let bbox = cog.lock().await.lnglat_bounds()?;
     let bbox_json_str = format!(
         "{{\n\
             \"lng_min\": {},\n\
@@ -83,35 +97,45 @@ async fn get_bounds(filename: &str) -> Result<HandlerResponse, Error> {
          }}",
         bbox.xmin, bbox.xmax, bbox.ymin, bbox.ymax
     );
-    Ok(Response::builder()
-        .status(StatusCode::OK)
-        .header("Content-Type", "application/json")
-        .body(bbox_json_str.into_bytes().into())?)
+    http_cache::respond_with_caching(
+        headers,
+        &etag,
+        "application/json",
+        bbox_json_str.into_bytes(),
+    )
 }
 
-async fn get_tile(filename: &str, z: u32, x: u64, y: u64) -> Result<HandlerResponse, Error> {
+async fn get_tile(
+    cache: &CogCache,
+    headers: &HeaderMap,
+    filename: &str,
+    z: u32,
+    x: u64,
+    y: u64,
+) -> Result<HandlerResponse, Error> {
     check_filename(filename)?;
     println!("get_tile {} {} {} {}", filename, z, x, y);
-    let mut cog = acog::COG::open(filename).await?;
+    let etag = http_cache::compute_etag(&[
+        "tile",
+        filename,
+        &z.to_string(),
+        &x.to_string(),
+        &y.to_string(),
+    ]);
+    if http_cache::not_modified(headers, &etag) {
+        return http_cache::not_modified_response();
+    }
+    let cog = cache.get_or_open(filename).await?;
+    let mut cog = cog.lock().await;
     let tile_data = extract_tile(&mut cog, TMSTileCoords::from_zxy(z, x, y)).await?;
-    // Encode to jpeg using turbojpeg and send back data
-    let img = turbojpeg::Image::<&[u8]> {
-        pixels: &tile_data.img.data,
-        width: tile_data.img.width,
-        height: tile_data.img.height,
-        pitch: tile_data.img.width * 3,
-        format: turbojpeg::PixelFormat::RGB,
-    };
-    let jpeg_buf = turbojpeg::compress(img, 95, turbojpeg::Subsamp::Sub2x2)?;
-    let jpeg_data: Vec<u8> = jpeg_buf.as_ref().to_vec();
-    Ok(Response::builder()
-        .status(StatusCode::OK)
-        .header("Content-Type", "image/jpeg")
-        .body(Full::from(jpeg_data))?)
-}
-
-#[derive(Clone)]
-struct MainService {}
+    let (encoded, content_type) = tile_encode::encode_tile(&tile_data.img)?;
+    http_cache::respond_with_caching(headers, &etag, content_type, encoded)
+}
+
+#[derive(Clone, Default)]
+struct MainService {
+    cog_cache: CogCache,
+}
 
 fn error_response<E>(
     method: &http::Method,
@@ -130,7 +154,12 @@ where
         .body("Internal error".to_string().into_bytes().into())
 }
 
-async fn router_inner(method: &Method, path: &str) -> Result<HandlerResponse, Error> {
+async fn router_inner(
+    cache: &CogCache,
+    headers: &HeaderMap,
+    method: &Method,
+    path: &str,
+) -> Result<HandlerResponse, Error> {
     // The path we get should always start with a slash
     let path_parts: Vec<&str> = path[1..].split('/').collect();
     // println!("path '{}', path_parts {:?}", path, path_parts);
@@ -155,13 +184,13 @@ async fn router_inner(method: &Method, path: &str) -> Result<HandlerResponse, Er
             let x = path_parts[n - 2].parse::<u64>()?;
             let y = path_parts[n - 1].parse::<u64>()?;
             let filename = path_parts[1..n - 3].join("/");
-            get_tile(&filename, z, x, y).await
+            get_tile(cache, headers, &filename, z, x, y).await
         } else if part_match(0, "bounds") && path_parts.len() >= 2 {
             // "bounds/raster.tif"
             // "bounds/example_data/local/raster.tif"
             // "bounds//vsis3/example_data/local/raster.tif"
             let filename = path_parts[1..].join("/");
-            get_bounds(&filename).await
+            get_bounds(cache, headers, &filename).await
         } else {
             four_oh_four(method, path).await
         }
@@ -170,8 +199,11 @@ async fn router_inner(method: &Method, path: &str) -> Result<HandlerResponse, Er
     }
 }
 
-async fn router(req: Request<body::Incoming>) -> Result<HandlerResponse, http::Error> {
-    let res = router_inner(req.method(), req.uri().path()).await;
+async fn router(
+    cache: &CogCache,
+    req: Request<body::Incoming>,
+) -> Result<HandlerResponse, http::Error> {
+    let res = router_inner(cache, req.headers(), req.method(), req.uri().path()).await;
     match res {
         Ok(v) => Ok(v),
         Err(Error::Http(e)) => Err(e),
@@ -186,7 +218,8 @@ impl Service<Request<body::Incoming>> for MainService {
     type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
 
     fn call(&self, req: Request<body::Incoming>) -> Self::Future {
-        Box::pin(router(req))
+        let cache = self.cog_cache.clone();
+        Box::pin(async move { router(&cache, req).await })
     }
 }
 
@@ -197,7 +230,7 @@ async fn main() {
         .await
         .unwrap();
     println!("listening on {}", listener.local_addr().unwrap());
-    let service = MainService {};
+    let service = MainService::default();
     loop {
         let (tcp, _) = listener.accept().await.unwrap();
         let io = TokioIo::new(tcp);
@@ -0,0 +1,62 @@
+use acog::image::{DataType, ImageBuffer};
+
+use crate::Error;
+
+impl From<png::EncodingError> for Error {
+    fn from(value: png::EncodingError) -> Self {
+        Error::Other(format!("png encoding error: {:?}", value))
+    }
+}
+
+/// Encodes a tile's pixels for the HTTP response, picking the format based on whether the tile
+/// carries an alpha band: JPEG can't represent transparency, so a tile with sparse/nodata pixels
+/// (surfaced as `has_alpha`) is served as PNG instead, at the cost of a bigger response than JPEG
+/// would give for the same opaque content.
+pub fn encode_tile(img: &ImageBuffer) -> Result<(Vec<u8>, &'static str), Error> {
+    if img.has_alpha {
+        Ok((encode_png(img)?, "image/png"))
+    } else {
+        Ok((encode_jpeg(img)?, "image/jpeg"))
+    }
+}
+
+fn encode_png(img: &ImageBuffer) -> Result<Vec<u8>, Error> {
+    if img.data_type != DataType::Uint8 {
+        return Err(Error::Other(format!(
+            "PNG tile encoding only supports Uint8 data, got {:?}",
+            img.data_type
+        )));
+    }
+    let color_type = match img.nbands {
+        2 => png::ColorType::GrayscaleAlpha,
+        4 => png::ColorType::Rgba,
+        nbands => {
+            return Err(Error::Other(format!(
+                "PNG tile encoding only supports 2 (grayscale+alpha) or 4 (rgba) bands, got {}",
+                nbands
+            )))
+        }
+    };
+
+    let mut out = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut out, img.width as u32, img.height as u32);
+        encoder.set_color(color_type);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&img.data)?;
+    }
+    Ok(out)
+}
+
+fn encode_jpeg(img: &ImageBuffer) -> Result<Vec<u8>, Error> {
+    let tj_img = turbojpeg::Image::<&[u8]> {
+        pixels: &img.data,
+        width: img.width,
+        height: img.height,
+        pitch: img.width * 3,
+        format: turbojpeg::PixelFormat::RGB,
+    };
+    let jpeg_buf = turbojpeg::compress(tj_img, 95, turbojpeg::Subsamp::Sub2x2)?;
+    Ok(jpeg_buf.as_ref().to_vec())
+}
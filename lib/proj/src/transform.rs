@@ -13,11 +13,12 @@ pub struct Transform {
 pub type Coordinate = (f64, f64);
 
 impl Transform {
-    // `from_epsg` and `to_epsg` should be EPSG identifiers like 4326 or 3857
-    pub fn new(from_epsg: u16, to_epsg: u16) -> Result<Transform, Error> {
+    // `from_crs` and `to_crs` are PROJ CRS definitions - either an `"EPSG:<code>"` string or a
+    // PROJ4/WKT definition string, e.g. `"+proj=utm +zone=32 +datum=WGS84"`
+    pub fn new(from_crs: &str, to_crs: &str) -> Result<Transform, Error> {
         let mut context = Context::new();
-        let c_source_crs = CString::new(format!("EPSG:{}", from_epsg))?;
-        let c_target_crs = CString::new(format!("EPSG:{}", to_epsg))?;
+        let c_source_crs = CString::new(from_crs)?;
+        let c_target_crs = CString::new(to_crs)?;
         // TODO: Create PJ_AREA using input raster bbox and output tile bbox
         // https://proj.org/en/9.3/development/reference/functions.html#c.proj_area_create
         // => This should lead to more precise transforms when there can be ambiguity
@@ -82,7 +83,7 @@ mod tests {
 
     #[test]
     fn test_transform_4326_4326() {
-        let t = Transform::new(4326, 4326).unwrap();
+        let t = Transform::new("EPSG:4326", "EPSG:4326").unwrap();
         let v0 = (42.0, -43.0);
         let v1 = t.transform(v0);
         assert_float_eq(v1.0, 42.0, 1e-5);
@@ -94,7 +95,7 @@ mod tests {
     #[test]
     fn test_transform_4326_3857() {
         // https://epsg.io/transform#s_srs=4326&t_srs=3857&x=42.0000000&y=-43.0000000
-        let t = Transform::new(4326, 3857).unwrap();
+        let t = Transform::new("EPSG:4326", "EPSG:3857").unwrap();
         let v0 = (42.0, -43.0);
         let v1 = t.transform(v0);
         assert_float_eq(v1.0, 4675418.613317491, 1e-5);
@@ -115,6 +115,68 @@ fn read_subimage(
     })
 }
 
+/// Writes `data` out to `filename` as a Cloud Optimized GeoTIFF. `data` must hold
+/// `width * height * nbands` samples of `dtype` ("uint8" or "float32"), row-major and interleaved
+/// by pixel. `epsg_code`/`ul_x`/`ul_y`/`x_res`/`y_res` describe the raster's CRS and (unrotated)
+/// affine geotransform - see `acog::Geotransform`.
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+fn write_cog(
+    py: Python,
+    filename: String,
+    data: Vec<u8>,
+    width: usize,
+    height: usize,
+    nbands: usize,
+    dtype: String,
+    epsg_code: u16,
+    ul_x: f64,
+    ul_y: f64,
+    x_res: f64,
+    y_res: f64,
+) -> PyResult<&PyAny> {
+    use ::acog::{Crs, Georeference, Geotransform, UnitOfMeasure, WriteOptions};
+
+    let data_type = match dtype.as_str() {
+        "uint8" => DataType::Uint8,
+        "float32" => DataType::Float32,
+        _ => {
+            return Err(PyRuntimeError::new_err(format!(
+                "Unsupported dtype {:?}",
+                dtype
+            )))
+        }
+    };
+    let image = ImageBuffer {
+        width,
+        height,
+        nbands,
+        has_alpha: false,
+        data_type,
+        nodata: None,
+        data,
+    };
+    let georeference = Georeference {
+        crs: Crs::decode(epsg_code),
+        unit: UnitOfMeasure::LinearMeter,
+        geo_transform: Geotransform {
+            ul_x,
+            ul_y,
+            x_res,
+            y_res,
+            x_rotation: 0.0,
+            y_rotation: 0.0,
+        },
+    };
+
+    pyo3_asyncio::tokio::future_into_py(py, async move {
+        match ::acog::COG::write(&filename, &image, &georeference, &WriteOptions::default()).await {
+            Ok(()) => Ok(()),
+            Err(e) => Err(PyRuntimeError::new_err(format!("{:?}", e))),
+        }
+    })
+}
+
 #[pyfunction]
 fn get_bounds(py: Python, filename: String) -> PyResult<&PyAny> {
     pyo3_asyncio::tokio::future_into_py(py, async move {
@@ -137,6 +199,7 @@ fn acog(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(read_tile, m)?)?;
     m.add_function(wrap_pyfunction!(read_subimage, m)?)?;
     m.add_function(wrap_pyfunction!(get_bounds, m)?)?;
+    m.add_function(wrap_pyfunction!(write_cog, m)?)?;
     m.add_class::<PyImage>()?;
     m.add_class::<BBox>()?;
     Ok(())
@@ -1,5 +1,5 @@
 use acog::npy::write_to_npy;
-use acog::Error;
+use acog::{Error, ImageRect};
 use std::env;
 
 #[tokio::main(flavor = "current_thread")]
@@ -30,19 +30,17 @@ async fn main() -> Result<(), Error> {
         );
     }
     let overview = &cog.overviews[overview_index];
-    let img_data = overview
+    let rect = ImageRect {
+        i_from: 0,
+        j_from: 0,
+        i_to: overview.height,
+        j_to: overview.width,
+    };
+    let img = overview
         .make_reader(&mut cog.source)
         .await?
-        .read_image(&mut cog.source)
+        .read_image_part(&mut cog.source, &rect)
         .await?;
-    write_to_npy(
-        "img.npy",
-        img_data,
-        [
-            overview.height as usize,
-            overview.width as usize,
-            overview.nbands as usize,
-        ],
-    )?;
+    write_to_npy("img.npy", &img)?;
     Ok(())
 }
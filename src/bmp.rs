@@ -0,0 +1,155 @@
+/// Utility functions to read/write uncompressed BMP bitmaps - like `ppm`/`tga`, only used for
+/// debugging non-RGB8 `ImageBuffer`s and not meant as a general purpose BMP library.
+use crate::image::{DataType, ImageBuffer};
+use crate::Error;
+use std::io::Write;
+use zune_bmp::BmpDecoder;
+use zune_core::bytestream::ZCursor;
+
+const FILE_HEADER_SIZE: u32 = 14;
+const INFO_HEADER_SIZE: u32 = 40;
+
+pub fn write_to_bmp(filename: &str, img: &ImageBuffer) -> Result<(), Error> {
+    if img.data_type != DataType::Uint8 {
+        return Err(Error::OtherError(format!(
+            "Only uint8 images are supported, got dtype={:?}",
+            img.data_type
+        )));
+    }
+    if img.nbands != 3 && img.nbands != 4 {
+        return Err(Error::OtherError(format!(
+            "Only RGB or RGBA images are supported, got nbands={}",
+            img.nbands
+        )));
+    }
+
+    let bpp: u32 = if img.nbands == 4 { 32 } else { 24 };
+    // BMP rows are padded to a 4-byte boundary - a no-op at 32bpp, since 4 bytes/pixel is already
+    // aligned.
+    let row_bytes = img.width * img.nbands;
+    let row_padding = (4 - row_bytes % 4) % 4;
+    let padded_row_bytes = row_bytes + row_padding;
+    let pixel_data_size = padded_row_bytes * img.height;
+    let pixel_data_offset = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+    let file_size = pixel_data_offset + pixel_data_size as u32;
+
+    let mut file = std::fs::File::create(filename)?;
+
+    // BITMAPFILEHEADER
+    file.write_all(b"BM")?;
+    file.write_all(&file_size.to_le_bytes())?;
+    file.write_all(&0u32.to_le_bytes())?; // reserved
+    file.write_all(&pixel_data_offset.to_le_bytes())?;
+
+    // BITMAPINFOHEADER (BITMAPINFOHEADER variant - the common one GDI/most readers expect)
+    file.write_all(&INFO_HEADER_SIZE.to_le_bytes())?;
+    file.write_all(&(img.width as i32).to_le_bytes())?;
+    // A positive height means the pixel data is stored bottom-up, per the BMP spec.
+    file.write_all(&(img.height as i32).to_le_bytes())?;
+    file.write_all(&1u16.to_le_bytes())?; // planes
+    file.write_all(&(bpp as u16).to_le_bytes())?;
+    file.write_all(&0u32.to_le_bytes())?; // compression: BI_RGB (none)
+    file.write_all(&(pixel_data_size as u32).to_le_bytes())?;
+    file.write_all(&0i32.to_le_bytes())?; // x pixels per meter
+    file.write_all(&0i32.to_le_bytes())?; // y pixels per meter
+    file.write_all(&0u32.to_le_bytes())?; // colors used
+    file.write_all(&0u32.to_le_bytes())?; // important colors
+
+    // Pixel data is stored BGR(A), bottom row first.
+    let padding = vec![0u8; row_padding];
+    for row in (0..img.height).rev() {
+        for col in 0..img.width {
+            let offset = (row * img.width + col) * img.nbands;
+            let r = img.data[offset];
+            let g = img.data[offset + 1];
+            let b = img.data[offset + 2];
+            if img.nbands == 4 {
+                let a = img.data[offset + 3];
+                file.write_all(&[b, g, r, a])?;
+            } else {
+                file.write_all(&[b, g, r])?;
+            }
+        }
+        file.write_all(&padding)?;
+    }
+    Ok(())
+}
+
+pub fn read_bmp(filename: &str) -> Result<ImageBuffer, Error> {
+    let data = std::fs::read(filename)?;
+    let mut decoder = BmpDecoder::new(ZCursor::new(data));
+    let pixels = decoder.decode()?;
+    let (width, height) = decoder
+        .dimensions()
+        .ok_or_else(|| Error::OtherError("BMP: could not determine image dimensions".to_string()))?;
+    let nbands = pixels.len() / (width * height);
+    if nbands * width * height != pixels.len() {
+        return Err(Error::OtherError(format!(
+            "BMP: decoded {} bytes doesn't evenly divide into {}x{} pixels",
+            pixels.len(),
+            width,
+            height
+        )));
+    }
+    Ok(ImageBuffer {
+        width,
+        height,
+        nbands,
+        has_alpha: nbands == 4,
+        data_type: DataType::Uint8,
+        nodata: None,
+        data: pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::{DataType, ImageBuffer};
+
+    #[test]
+    fn test_write_read_bmp_rgb() {
+        let data = vec![0u8, 0u8, 0u8, 255u8, 255u8, 255u8];
+        super::write_to_bmp(
+            "/tmp/test.bmp",
+            &ImageBuffer {
+                width: 2,
+                height: 1,
+                nbands: 3,
+                has_alpha: false,
+                data_type: DataType::Uint8,
+                nodata: None,
+                data: data.clone(),
+            },
+        )
+        .unwrap();
+        let actual_img = super::read_bmp("/tmp/test.bmp").unwrap();
+        assert_eq!(actual_img.width, 2);
+        assert_eq!(actual_img.height, 1);
+        assert_eq!(actual_img.nbands, 3);
+        assert_eq!(actual_img.data, data);
+    }
+
+    #[test]
+    fn test_write_read_bmp_rgba() {
+        let data = vec![0u8, 0u8, 0u8, 127u8, 255u8, 255u8, 255u8, 0u8];
+        super::write_to_bmp(
+            "/tmp/test_rgba.bmp",
+            &ImageBuffer {
+                width: 2,
+                height: 1,
+                nbands: 4,
+                has_alpha: true,
+                data_type: DataType::Uint8,
+                nodata: None,
+                data: data.clone(),
+            },
+        )
+        .unwrap();
+        let actual_img = super::read_bmp("/tmp/test_rgba.bmp").unwrap();
+        assert_eq!(actual_img.width, 2);
+        assert_eq!(actual_img.height, 1);
+        assert_eq!(actual_img.nbands, 4);
+        assert!(actual_img.has_alpha);
+        assert_eq!(actual_img.data, data);
+    }
+}
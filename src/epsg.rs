@@ -28,12 +28,50 @@ impl UnitOfMeasure {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+/// The projection parameters carried by a GeoTIFF's ProjectionGeoKeys when ProjectedCSTypeGeoKey
+/// is 32767 ("user-defined"), i.e. a CRS that isn't a plain EPSG code. Not every field is used by
+/// every coordinate transformation method - e.g. a Transverse Mercator only needs
+/// `nat_origin_long`/`nat_origin_lat`/`scale_at_nat_origin`/`false_easting`/`false_northing`, while
+/// a Lambert Conformal Conic (2SP) uses the std parallels and false origin instead.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct UserDefinedProjectionParameters {
+    pub std_parallel_1: Option<f64>,
+    pub std_parallel_2: Option<f64>,
+    pub nat_origin_long: Option<f64>,
+    pub nat_origin_lat: Option<f64>,
+    pub false_easting: Option<f64>,
+    pub false_northing: Option<f64>,
+    pub false_origin_long: Option<f64>,
+    pub false_origin_lat: Option<f64>,
+    pub scale_at_nat_origin: Option<f64>,
+    pub azimuth_angle: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Crs {
     PseudoMercator,
+    // A CRS built from a ProjCoordTransGeoKey (e.g. transverse mercator, Lambert conformal conic)
+    // plus its own parameters, rather than looked up from an EPSG code
+    UserDefined {
+        coord_trans_method: u16,
+        parameters: UserDefinedProjectionParameters,
+    },
     Unknown(u16),
 }
 
+// GeoTIFF "Coordinate Transformation Codes" (GeoTIFF spec 6.3.3.3) for the ProjCoordTransGeoKey
+// values we know how to turn into a PROJ definition. Not exhaustive - only the ones common enough
+// to show up in real-world user-defined GeoTIFFs (UTM-style transverse mercator, Lambert conformal
+// conic, etc.) are mapped; anything else falls back to `Error::UnsupportedProjection`.
+const CT_TRANSVERSE_MERCATOR: u16 = 1;
+const CT_MERCATOR: u16 = 7;
+const CT_LAMBERT_CONF_CONIC_2SP: u16 = 8;
+const CT_LAMBERT_CONF_CONIC_1SP: u16 = 9;
+const CT_ALBERS_EQUAL_AREA: u16 = 11;
+const CT_STEREOGRAPHIC: u16 = 14;
+const CT_POLAR_STEREOGRAPHIC: u16 = 15;
+const CT_EQUIRECTANGULAR: u16 = 17;
+
 impl Crs {
     pub fn decode(v: u16) -> Crs {
         match v {
@@ -42,10 +80,107 @@ impl Crs {
         }
     }
 
-    pub fn epsg_code(&self) -> u16 {
+    /// The PROJ CRS definition to pass to `proj::Transform::new` for this CRS - either an
+    /// `"EPSG:<code>"` string, or (for a `UserDefined` CRS) a `"+proj=..."` string built from its
+    /// ProjCoordTransGeoKey method and parameters.
+    pub fn proj_spec(&self) -> Result<String, Error> {
         match self {
-            Crs::PseudoMercator => 3857,
-            Crs::Unknown(v) => *v,
+            Crs::PseudoMercator => Ok("EPSG:3857".to_string()),
+            Crs::Unknown(v) => Ok(format!("EPSG:{}", v)),
+            Crs::UserDefined {
+                coord_trans_method,
+                parameters,
+            } => user_defined_proj_spec(*coord_trans_method, parameters),
         }
     }
 }
+
+fn push_param(spec: &mut String, flag: &str, value: Option<f64>) {
+    if let Some(v) = value {
+        spec.push_str(&format!(" +{}={}", flag, v));
+    }
+}
+
+/// Builds a `"+proj=..."` PROJ string from a ProjCoordTransGeoKey method and its parameters. We
+/// don't decode the datum/ellipsoid GeoKeys yet, so this assumes WGS84 - see
+/// `UserDefinedProjectionParameters` doc comment for which fields apply to which method.
+fn user_defined_proj_spec(
+    coord_trans_method: u16,
+    p: &UserDefinedProjectionParameters,
+) -> Result<String, Error> {
+    let mut spec = match coord_trans_method {
+        CT_TRANSVERSE_MERCATOR => {
+            let mut s = "+proj=tmerc".to_string();
+            push_param(&mut s, "lon_0", p.nat_origin_long);
+            push_param(&mut s, "lat_0", p.nat_origin_lat);
+            push_param(&mut s, "k_0", p.scale_at_nat_origin);
+            push_param(&mut s, "x_0", p.false_easting);
+            push_param(&mut s, "y_0", p.false_northing);
+            s
+        }
+        CT_MERCATOR => {
+            let mut s = "+proj=merc".to_string();
+            push_param(&mut s, "lon_0", p.nat_origin_long);
+            push_param(&mut s, "lat_ts", p.nat_origin_lat);
+            push_param(&mut s, "k_0", p.scale_at_nat_origin);
+            push_param(&mut s, "x_0", p.false_easting);
+            push_param(&mut s, "y_0", p.false_northing);
+            s
+        }
+        CT_LAMBERT_CONF_CONIC_2SP => {
+            let mut s = "+proj=lcc".to_string();
+            push_param(&mut s, "lat_1", p.std_parallel_1);
+            push_param(&mut s, "lat_2", p.std_parallel_2);
+            push_param(&mut s, "lon_0", p.false_origin_long);
+            push_param(&mut s, "lat_0", p.false_origin_lat);
+            push_param(&mut s, "x_0", p.false_easting);
+            push_param(&mut s, "y_0", p.false_northing);
+            s
+        }
+        CT_LAMBERT_CONF_CONIC_1SP => {
+            let mut s = "+proj=lcc".to_string();
+            push_param(&mut s, "lat_1", p.nat_origin_lat);
+            push_param(&mut s, "lat_0", p.nat_origin_lat);
+            push_param(&mut s, "lon_0", p.nat_origin_long);
+            push_param(&mut s, "k_0", p.scale_at_nat_origin);
+            push_param(&mut s, "x_0", p.false_easting);
+            push_param(&mut s, "y_0", p.false_northing);
+            s
+        }
+        CT_ALBERS_EQUAL_AREA => {
+            let mut s = "+proj=aea".to_string();
+            push_param(&mut s, "lat_1", p.std_parallel_1);
+            push_param(&mut s, "lat_2", p.std_parallel_2);
+            push_param(&mut s, "lon_0", p.false_origin_long);
+            push_param(&mut s, "lat_0", p.false_origin_lat);
+            push_param(&mut s, "x_0", p.false_easting);
+            push_param(&mut s, "y_0", p.false_northing);
+            s
+        }
+        CT_STEREOGRAPHIC | CT_POLAR_STEREOGRAPHIC => {
+            let mut s = "+proj=stere".to_string();
+            push_param(&mut s, "lon_0", p.nat_origin_long);
+            push_param(&mut s, "lat_0", p.nat_origin_lat);
+            push_param(&mut s, "k_0", p.scale_at_nat_origin);
+            push_param(&mut s, "x_0", p.false_easting);
+            push_param(&mut s, "y_0", p.false_northing);
+            s
+        }
+        CT_EQUIRECTANGULAR => {
+            let mut s = "+proj=eqc".to_string();
+            push_param(&mut s, "lon_0", p.nat_origin_long);
+            push_param(&mut s, "lat_ts", p.nat_origin_lat);
+            push_param(&mut s, "x_0", p.false_easting);
+            push_param(&mut s, "y_0", p.false_northing);
+            s
+        }
+        v => {
+            return Err(Error::UnsupportedProjection(format!(
+                "Don't know how to build a PROJ definition for ProjCoordTransGeoKey={}",
+                v
+            )))
+        }
+    };
+    spec.push_str(" +ellps=WGS84 +units=m +no_defs");
+    Ok(spec)
+}
@@ -18,12 +18,20 @@ pub enum Error {
     GeoKeyHasWrongType(KeyID, KeyValue),
     UnsupportedProjection(String),
     UnsupportedCompression(String),
+    UnsupportedDataType(String),
     UnsupportedUnit(String),
     OutOfBoundsRead(String),
     UnsupportedCOG(String),
     ReqwestError(reqwest::Error),
     ProjError(proj::Error),
     OtherError(String),
+    // AWS SigV4 signing could not find usable access/secret keys (env, profile file, IMDS)
+    MissingCredentials(String),
+    // AWS SigV4 signing was given a region that isn't well-formed (e.g. empty, or containing
+    // characters outside `[a-z0-9-]`)
+    InvalidRegion(String),
+    // HMAC computation failed, e.g. because the signing key had an invalid length
+    SigningError(String),
 }
 
 impl From<io::Error> for Error {
@@ -82,3 +90,9 @@ impl From<zune_jpeg::errors::DecodeErrors> for Error {
         Error::OtherError(format!("JPEG error: {:?}", value))
     }
 }
+
+impl From<zune_bmp::errors::BmpDecoderErrors> for Error {
+    fn from(value: zune_bmp::errors::BmpDecoderErrors) -> Self {
+        Error::OtherError(format!("BMP error: {:?}", value))
+    }
+}
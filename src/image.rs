@@ -1,26 +1,170 @@
+use crate::tiff::tags::ColorMap;
 use crate::Error;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DataType {
     Uint8,
+    Int16,
+    Uint16,
+    Int32,
+    Uint32,
     Float32,
+    Float64,
 }
 
 impl DataType {
     pub fn size_bytes(&self) -> usize {
         match self {
             DataType::Uint8 => 1,
-            DataType::Float32 => 4,
+            DataType::Int16 | DataType::Uint16 => 2,
+            DataType::Int32 | DataType::Uint32 | DataType::Float32 => 4,
+            DataType::Float64 => 8,
         }
     }
+
+    // All sample data is stored little-endian, matching the TIFF files this is read from/written
+    // to (classic TIFF supports both byte orders, but only little-endian is supported here - see
+    // `tiff::writer`).
+    fn read_sample(&self, data: &[u8], offset: usize) -> f64 {
+        match self {
+            DataType::Uint8 => data[offset] as f64,
+            DataType::Int16 => {
+                i16::from_le_bytes(data[offset..offset + 2].try_into().unwrap()) as f64
+            }
+            DataType::Uint16 => {
+                u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap()) as f64
+            }
+            DataType::Int32 => {
+                i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as f64
+            }
+            DataType::Uint32 => {
+                u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as f64
+            }
+            DataType::Float32 => {
+                f32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as f64
+            }
+            DataType::Float64 => f64::from_le_bytes(data[offset..offset + 8].try_into().unwrap()),
+        }
+    }
+
+    fn write_sample(&self, data: &mut [u8], offset: usize, value: f64) {
+        match self {
+            DataType::Uint8 => data[offset] = value.round().clamp(0.0, 255.0) as u8,
+            DataType::Int16 => data[offset..offset + 2].copy_from_slice(
+                &(value.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16).to_le_bytes(),
+            ),
+            DataType::Uint16 => data[offset..offset + 2]
+                .copy_from_slice(&(value.round().clamp(0.0, u16::MAX as f64) as u16).to_le_bytes()),
+            DataType::Int32 => data[offset..offset + 4].copy_from_slice(
+                &(value.round().clamp(i32::MIN as f64, i32::MAX as f64) as i32).to_le_bytes(),
+            ),
+            DataType::Uint32 => data[offset..offset + 4]
+                .copy_from_slice(&(value.round().clamp(0.0, u32::MAX as f64) as u32).to_le_bytes()),
+            DataType::Float32 => {
+                data[offset..offset + 4].copy_from_slice(&(value as f32).to_le_bytes())
+            }
+            DataType::Float64 => data[offset..offset + 8].copy_from_slice(&value.to_le_bytes()),
+        }
+    }
+
+    /// The maximum representable sample value for this type, used by `ImageBuffer::
+    /// invert_grayscale` to flip a `WhiteIsZero` image's samples (`value' = max_value - value`).
+    fn max_value(&self) -> f64 {
+        match self {
+            DataType::Uint8 => u8::MAX as f64,
+            DataType::Int16 => i16::MAX as f64,
+            DataType::Uint16 => u16::MAX as f64,
+            DataType::Int32 => i32::MAX as f64,
+            DataType::Uint32 => u32::MAX as f64,
+            DataType::Float32 | DataType::Float64 => 1.0,
+        }
+    }
+}
+
+/// Resampling kernel used by [`ImageBuffer::resample_to`] and [`ImageBuffer::sample_at`],
+/// mirroring the choice GDAL exposes for `RasterIO`/`gdalwarp` (`-r nearest`/`-r bilinear`/`-r
+/// cubic`/`-r average`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResampleAlg {
+    /// Picks the value of the closest source pixel. Cheap, but blocky when upsampling.
+    Nearest,
+    /// Blends the 4 source pixels surrounding the sample point, weighted by how close each one is.
+    Bilinear,
+    /// Convolves the 4x4 source pixels surrounding the sample point with a Catmull-Rom kernel
+    /// (`a = -0.5`). Sharper than bilinear, at the cost of a bigger neighborhood to read.
+    Cubic,
+    /// Box-averages every source pixel whose center falls within `radius_x`/`radius_y` source
+    /// pixels of the sample point. Unlike the other kernels, which only ever look at a handful of
+    /// neighbors, this is meant for downsampling: a bilinear/cubic kernel would alias away most of
+    /// the source data, while averaging over the full source footprint that maps onto one output
+    /// pixel doesn't.
+    Average { radius_x: f64, radius_y: f64 },
+}
+
+/// Catmull-Rom cubic convolution weight (`a = -0.5`) for a tap `t` pixels away from the sample
+/// point, per Keys 1981 "Cubic convolution interpolation for digital image processing".
+fn cubic_weight(t: f64) -> f64 {
+    let a = -0.5;
+    let t = t.abs();
+    if t <= 1.0 {
+        (a + 2.0) * t.powi(3) - (a + 3.0) * t.powi(2) + 1.0
+    } else if t < 2.0 {
+        a * t.powi(3) - 5.0 * a * t.powi(2) + 8.0 * a * t - 4.0 * a
+    } else {
+        0.0
+    }
+}
+
+/// Equal-weight taps over every source pixel whose center falls in
+/// `[cx - radius_x, cx + radius_x) x [cy - radius_y, cy + radius_y)`, clamped to the image bounds
+/// and always including at least the pixel under `(cx, cy)` itself (e.g. when the radius rounds
+/// down to less than half a pixel). Shared by `ResampleAlg::Average`'s `resample_to`/`sample_at`
+/// arms.
+fn box_average_taps(
+    cx: f64,
+    cy: f64,
+    radius_x: f64,
+    radius_y: f64,
+    width: usize,
+    height: usize,
+) -> Vec<(usize, usize, f64)> {
+    let j0 = (cx - radius_x).floor().clamp(0.0, (width - 1) as f64) as usize;
+    let j1 = (cx + radius_x).ceil().clamp(1.0, width as f64) as usize;
+    let i0 = (cy - radius_y).floor().clamp(0.0, (height - 1) as f64) as usize;
+    let i1 = (cy + radius_y).ceil().clamp(1.0, height as f64) as usize;
+    let (j1, i1) = (j1.max(j0 + 1), i1.max(i0 + 1));
+    let weight = 1.0 / ((i1 - i0) * (j1 - j0)) as f64;
+    let mut taps = Vec::with_capacity((i1 - i0) * (j1 - j0));
+    for i in i0..i1 {
+        for j in j0..j1 {
+            taps.push((i, j, weight));
+        }
+    }
+    taps
 }
 
+/// Number of histogram bins [`ImageBuffer::to_rgb_auto_stretch`] uses to approximate a band's
+/// value distribution.
+const STRETCH_HISTOGRAM_BINS: usize = 4096;
+
+/// Default percentile bounds for [`ImageBuffer::to_rgb_auto_stretch`], matching the "2%
+/// linear"/cumulative-count-cut stretch GDAL and QGIS default to.
+pub const DEFAULT_STRETCH_LOW_PERCENTILE: f64 = 2.0;
+pub const DEFAULT_STRETCH_HIGH_PERCENTILE: f64 = 98.0;
+
+#[derive(Clone)]
 pub struct ImageBuffer {
     pub width: usize,
     pub height: usize,
     pub nbands: usize,
     pub has_alpha: bool,
     pub data_type: DataType,
+    /// Sentinel value marking "no data" pixels when there's no dedicated alpha band - e.g. a
+    /// GeoTIFF's `GDALNoData` tag. A pixel is treated as nodata when every non-alpha band equals
+    /// this value. `to_rgb`/`to_rgb_with_bounds` turn this into a real alpha band (0 for nodata
+    /// pixels) in their output, and `resample_to`/`sample_at` already skip nodata neighbors the
+    /// same way they skip `alpha == 0` ones (see `is_valid`).
+    pub nodata: Option<f64>,
     // The image data stored in row-major order, packed by pixel
     pub data: Vec<u8>,
 }
@@ -28,39 +172,415 @@ pub struct ImageBuffer {
 impl ImageBuffer {
     // Converts this image buffer to a RGB image buffer by:
     // - selecting 3 bands
-    // - normalizing them with vmin/vmax
+    // - normalizing them with the same vmin/vmax
+    //
+    // See `to_rgb_with_bounds` for independent per-band bounds and `to_rgb_auto_stretch` to
+    // derive the bounds automatically.
     pub fn to_rgb(&self, bands: &[usize; 3], vmin: f64, vmax: f64) -> Result<ImageBuffer, Error> {
-        let mut out_data = vec![0u8; self.width * self.height * 3];
-        match self.data_type {
-            DataType::Uint8 => todo!("Uint8 to RGB conversion not implemented"),
-            DataType::Float32 => {
-                for i in 0..self.height {
-                    for j in 0..self.width {
-                        for (bi, b) in bands.iter().enumerate() {
-                            let offset = i * self.width * self.nbands * self.data_type.size_bytes()
-                                + j * self.nbands * self.data_type.size_bytes()
-                                + b * self.data_type.size_bytes();
-                            let v: f32 = f32::from_le_bytes(
-                                self.data[offset..offset + 4].try_into().unwrap(),
-                            );
-                            let out_offset = i * self.width * 3 + j * 3 + bi;
-                            out_data[out_offset] =
-                                (255.0f64 * (v as f64 - vmin) / (vmax - vmin)) as u8;
-                        }
-                    }
+        self.to_rgb_with_bounds(bands, &[(vmin, vmax); 3])
+    }
+
+    // Converts this image buffer to a RGB image buffer by:
+    // - selecting 3 bands
+    // - normalizing each one independently with its own (vmin, vmax)
+    //
+    // Always emits `DataType::Uint8`, regardless of this image's own sample depth - the
+    // (vmin, vmax) stretch already maps samples onto the 0-255 display range.
+    pub fn to_rgb_with_bounds(
+        &self,
+        bands: &[usize; 3],
+        bounds: &[(f64, f64); 3],
+    ) -> Result<ImageBuffer, Error> {
+        let sample_bytes = self.data_type.size_bytes();
+        let self_alpha_band = if self.has_alpha {
+            Some(self.nbands - 1)
+        } else {
+            None
+        };
+        // Emit a 4th (alpha) band whenever this image already carries one, or has a nodata
+        // sentinel that needs to be turned into one, so nodata/masked pixels come out transparent
+        // instead of a normal (and misleading) RGB color.
+        let has_alpha = self.has_alpha || self.nodata.is_some();
+        let out_nbands = if has_alpha { 4 } else { 3 };
+        let mut out_data = vec![0u8; self.width * self.height * out_nbands];
+        for i in 0..self.height {
+            for j in 0..self.width {
+                for (bi, b) in bands.iter().enumerate() {
+                    let offset = self.pixel_offset(i, j, sample_bytes) + b * sample_bytes;
+                    let v = self.data_type.read_sample(&self.data, offset);
+                    let (vmin, vmax) = bounds[bi];
+                    let out_offset = i * self.width * out_nbands + j * out_nbands + bi;
+                    out_data[out_offset] =
+                        (255.0 * (v - vmin) / (vmax - vmin)).clamp(0.0, 255.0) as u8;
+                }
+                if has_alpha {
+                    let out_offset = i * self.width * out_nbands + j * out_nbands + 3;
+                    out_data[out_offset] =
+                        if self.is_valid(i, j, self_alpha_band, sample_bytes) {
+                            255
+                        } else {
+                            0
+                        };
                 }
             }
         }
         Ok(ImageBuffer {
             width: self.width,
             height: self.height,
-            nbands: 3,
-            has_alpha: false,
+            nbands: out_nbands,
+            has_alpha,
             data_type: DataType::Uint8,
+            nodata: None,
             data: out_data,
         })
     }
 
+    /// Like [`ImageBuffer::to_rgb`], but derives each band's `(vmin, vmax)` stretch bounds
+    /// automatically instead of requiring the caller to know the value range up front: a
+    /// `STRETCH_HISTOGRAM_BINS`-bin histogram is built per band from a subsampled scan of the
+    /// buffer, and `low_percentile`/`high_percentile` of its cumulative distribution become
+    /// vmin/vmax. Returns the bounds alongside the RGB buffer so callers (e.g. the tiler) can
+    /// reuse the same bounds across tiles instead of re-deriving, and potentially diverging,
+    /// them per tile. Like `to_rgb`, always emits `DataType::Uint8`.
+    pub fn to_rgb_auto_stretch(
+        &self,
+        bands: &[usize; 3],
+        low_percentile: f64,
+        high_percentile: f64,
+    ) -> Result<(ImageBuffer, [(f64, f64); 3]), Error> {
+        let bounds = [
+            self.band_percentile_bounds(bands[0], low_percentile, high_percentile),
+            self.band_percentile_bounds(bands[1], low_percentile, high_percentile),
+            self.band_percentile_bounds(bands[2], low_percentile, high_percentile),
+        ];
+        Ok((self.to_rgb_with_bounds(bands, &bounds)?, bounds))
+    }
+
+    /// Pick `(vmin, vmax)` for `band` at `low_percentile`/`high_percentile` of its cumulative
+    /// value distribution, approximated by a `STRETCH_HISTOGRAM_BINS`-bin histogram built from a
+    /// subsampled scan of the buffer (stepping over pixels so stretching a huge image stays
+    /// cheap).
+    fn band_percentile_bounds(
+        &self,
+        band: usize,
+        low_percentile: f64,
+        high_percentile: f64,
+    ) -> (f64, f64) {
+        let sample_bytes = self.data_type.size_bytes();
+        let npixels = self.width * self.height;
+        let target_samples = STRETCH_HISTOGRAM_BINS * STRETCH_HISTOGRAM_BINS;
+        let stride = (npixels / target_samples).max(1);
+
+        let mut values = Vec::with_capacity(npixels / stride + 1);
+        let mut idx = 0;
+        while idx < npixels {
+            let (i, j) = (idx / self.width, idx % self.width);
+            let offset = self.pixel_offset(i, j, sample_bytes) + band * sample_bytes;
+            values.push(self.data_type.read_sample(&self.data, offset));
+            idx += stride;
+        }
+
+        let vmin = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let vmax = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        if vmin >= vmax {
+            // Constant (or empty) band - nothing to stretch.
+            return (vmin, vmax);
+        }
+
+        let mut histogram = vec![0u32; STRETCH_HISTOGRAM_BINS];
+        let bin_width = (vmax - vmin) / STRETCH_HISTOGRAM_BINS as f64;
+        for v in &values {
+            let bin = (((v - vmin) / bin_width) as usize).min(STRETCH_HISTOGRAM_BINS - 1);
+            histogram[bin] += 1;
+        }
+
+        let total = values.len() as f64;
+        let low_count = total * (low_percentile / 100.0);
+        let high_count = total * (high_percentile / 100.0);
+        let (mut vmin_out, mut vmax_out) = (vmin, vmax);
+        let mut cumulative = 0u32;
+        let mut found_low = false;
+        for (bin, &count) in histogram.iter().enumerate() {
+            cumulative += count;
+            if !found_low && cumulative as f64 >= low_count {
+                vmin_out = vmin + bin as f64 * bin_width;
+                found_low = true;
+            }
+            if cumulative as f64 >= high_count {
+                vmax_out = vmin + (bin + 1) as f64 * bin_width;
+                break;
+            }
+        }
+        (vmin_out, vmax_out)
+    }
+
+    /// Resamples this image to exactly `width`x`height`, using `alg` to pick/blend source
+    /// pixels. This is the kernel GDAL applies during `RasterIO` when the requested buffer size
+    /// doesn't match the source window.
+    pub fn resample_to(&self, width: usize, height: usize, alg: ResampleAlg) -> ImageBuffer {
+        let sample_bytes = self.data_type.size_bytes();
+        let mut out_data = vec![0u8; width * height * self.nbands * sample_bytes];
+        let scale_x = self.width as f64 / width as f64;
+        let scale_y = self.height as f64 / height as f64;
+        let alpha_band = if self.has_alpha {
+            Some(self.nbands - 1)
+        } else {
+            None
+        };
+        for dst_i in 0..height {
+            for dst_j in 0..width {
+                let out_offset = (dst_i * width * self.nbands + dst_j * self.nbands) * sample_bytes;
+                match alg {
+                    ResampleAlg::Nearest => {
+                        let src_i = (((dst_i as f64 + 0.5) * scale_y).round() as usize)
+                            .min(self.height - 1);
+                        let src_j =
+                            (((dst_j as f64 + 0.5) * scale_x).round() as usize).min(self.width - 1);
+                        let in_offset =
+                            (src_i * self.width * self.nbands + src_j * self.nbands) * sample_bytes;
+                        out_data[out_offset..out_offset + self.nbands * sample_bytes]
+                            .copy_from_slice(
+                                &self.data[in_offset..in_offset + self.nbands * sample_bytes],
+                            );
+                    }
+                    ResampleAlg::Bilinear => {
+                        let sx = (dst_j as f64 + 0.5) * scale_x - 0.5;
+                        let sy = (dst_i as f64 + 0.5) * scale_y - 0.5;
+                        let j0 = sx.floor().clamp(0.0, (self.width - 1) as f64) as usize;
+                        let i0 = sy.floor().clamp(0.0, (self.height - 1) as f64) as usize;
+                        let j1 = (j0 + 1).min(self.width - 1);
+                        let i1 = (i0 + 1).min(self.height - 1);
+                        let fj = (sx - j0 as f64).clamp(0.0, 1.0);
+                        let fi = (sy - i0 as f64).clamp(0.0, 1.0);
+                        // Taps in (i, j) order: top-left, top-right, bottom-left, bottom-right
+                        let taps = [
+                            (i0, j0, (1.0 - fi) * (1.0 - fj)),
+                            (i0, j1, (1.0 - fi) * fj),
+                            (i1, j0, fi * (1.0 - fj)),
+                            (i1, j1, fi * fj),
+                        ];
+                        self.weighted_sample(
+                            &taps,
+                            alpha_band,
+                            sample_bytes,
+                            &mut out_data,
+                            out_offset,
+                        );
+                    }
+                    ResampleAlg::Cubic => {
+                        let sx = (dst_j as f64 + 0.5) * scale_x - 0.5;
+                        let sy = (dst_i as f64 + 0.5) * scale_y - 0.5;
+                        let j_base = sx.floor();
+                        let i_base = sy.floor();
+                        let fj = sx - j_base;
+                        let fi = sy - i_base;
+                        let mut taps = Vec::with_capacity(16);
+                        for di in -1..=2 {
+                            let i =
+                                (i_base + di as f64).clamp(0.0, (self.height - 1) as f64) as usize;
+                            let wi = cubic_weight(di as f64 - fi);
+                            for dj in -1..=2 {
+                                let j = (j_base + dj as f64).clamp(0.0, (self.width - 1) as f64)
+                                    as usize;
+                                let wj = cubic_weight(dj as f64 - fj);
+                                taps.push((i, j, wi * wj));
+                            }
+                        }
+                        self.weighted_sample(
+                            &taps,
+                            alpha_band,
+                            sample_bytes,
+                            &mut out_data,
+                            out_offset,
+                        );
+                    }
+                    // The box footprint of one output pixel in source space is exactly
+                    // `scale_x`x`scale_y`, already known here, so the radii passed by the caller
+                    // (meaningful for `sample_at`, which has no such built-in scale) are ignored.
+                    ResampleAlg::Average { .. } => {
+                        let cx = (dst_j as f64 + 0.5) * scale_x;
+                        let cy = (dst_i as f64 + 0.5) * scale_y;
+                        let taps =
+                            box_average_taps(cx, cy, scale_x / 2.0, scale_y / 2.0, self.width, self.height);
+                        self.weighted_sample(
+                            &taps,
+                            alpha_band,
+                            sample_bytes,
+                            &mut out_data,
+                            out_offset,
+                        );
+                    }
+                }
+            }
+        }
+        ImageBuffer {
+            width,
+            height,
+            nbands: self.nbands,
+            has_alpha: self.has_alpha,
+            data_type: self.data_type,
+            nodata: self.nodata,
+            data: out_data,
+        }
+    }
+
+    fn pixel_offset(&self, i: usize, j: usize, sample_bytes: usize) -> usize {
+        (i * self.width * self.nbands + j * self.nbands) * sample_bytes
+    }
+
+    // A pixel is invalid (nodata) if its alpha band (when present) is 0, or if every non-alpha
+    // band equals `self.nodata` (when set). Both checks are independent masks applied on top of
+    // each other, so e.g. a stacked image can carry an explicit alpha band while its data bands
+    // still use a nodata sentinel.
+    fn is_valid(&self, i: usize, j: usize, alpha_band: Option<usize>, sample_bytes: usize) -> bool {
+        if let Some(band) = alpha_band {
+            let alpha = self.data_type.read_sample(
+                &self.data,
+                self.pixel_offset(i, j, sample_bytes) + band * sample_bytes,
+            );
+            if alpha == 0.0 {
+                return false;
+            }
+        }
+        if let Some(nodata) = self.nodata {
+            let visual_bands = match alpha_band {
+                Some(band) => band,
+                None => self.nbands,
+            };
+            let all_nodata = (0..visual_bands).all(|band| {
+                self.data_type.read_sample(
+                    &self.data,
+                    self.pixel_offset(i, j, sample_bytes) + band * sample_bytes,
+                ) == nodata
+            });
+            if all_nodata {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Same validity check as `is_valid`, but at fractional "corner-aligned" pixel coordinates
+    /// `(x, y)` (see `sample_at`), looking at the single nearest source pixel rather than
+    /// interpolating - a mask is a binary in/out decision, not a value to blend.
+    pub fn is_valid_at(&self, x: f64, y: f64) -> bool {
+        let sample_bytes = self.data_type.size_bytes();
+        let alpha_band = if self.has_alpha {
+            Some(self.nbands - 1)
+        } else {
+            None
+        };
+        let j = x.floor().clamp(0.0, (self.width - 1) as f64) as usize;
+        let i = y.floor().clamp(0.0, (self.height - 1) as f64) as usize;
+        self.is_valid(i, j, alpha_band, sample_bytes)
+    }
+
+    /// Samples this image at fractional "corner-aligned" pixel coordinates `(x, y)` - integer
+    /// values sit on pixel grid lines, pixel `(i, j)` spanning `[j, j+1) x [i, i+1)`, which is the
+    /// convention the tiler's `Warper::project_tile_pixel` returns - and writes the result for
+    /// every band into `out[out_offset..]`. `x`/`y` are clamped to the image bounds so callers
+    /// don't need to bounds-check beforehand.
+    ///
+    /// As with `resample_to`'s Bilinear/Cubic case, nodata pixels (`alpha == 0`) are dropped from
+    /// the weighted average instead of bleeding into a neighboring valid pixel, while the alpha
+    /// band itself always blends over the full neighborhood so edges fade out smoothly.
+    pub fn sample_at(&self, x: f64, y: f64, alg: ResampleAlg, out: &mut [u8], out_offset: usize) {
+        let sample_bytes = self.data_type.size_bytes();
+        let alpha_band = if self.has_alpha {
+            Some(self.nbands - 1)
+        } else {
+            None
+        };
+        match alg {
+            ResampleAlg::Nearest => {
+                let j = x.floor().clamp(0.0, (self.width - 1) as f64) as usize;
+                let i = y.floor().clamp(0.0, (self.height - 1) as f64) as usize;
+                let in_offset = self.pixel_offset(i, j, sample_bytes);
+                out[out_offset..out_offset + self.nbands * sample_bytes]
+                    .copy_from_slice(&self.data[in_offset..in_offset + self.nbands * sample_bytes]);
+            }
+            ResampleAlg::Bilinear => {
+                // Shift to pixel-center-aligned coordinates: pixel j's center sits at j + 0.5
+                let cx = x - 0.5;
+                let cy = y - 0.5;
+                let j0 = cx.floor().clamp(0.0, (self.width - 1) as f64) as usize;
+                let i0 = cy.floor().clamp(0.0, (self.height - 1) as f64) as usize;
+                let j1 = (j0 + 1).min(self.width - 1);
+                let i1 = (i0 + 1).min(self.height - 1);
+                let fj = (cx - j0 as f64).clamp(0.0, 1.0);
+                let fi = (cy - i0 as f64).clamp(0.0, 1.0);
+                let taps = [
+                    (i0, j0, (1.0 - fi) * (1.0 - fj)),
+                    (i0, j1, (1.0 - fi) * fj),
+                    (i1, j0, fi * (1.0 - fj)),
+                    (i1, j1, fi * fj),
+                ];
+                self.weighted_sample(&taps, alpha_band, sample_bytes, out, out_offset);
+            }
+            ResampleAlg::Cubic => {
+                let cx = x - 0.5;
+                let cy = y - 0.5;
+                let j_base = cx.floor();
+                let i_base = cy.floor();
+                let fj = cx - j_base;
+                let fi = cy - i_base;
+                let mut taps = Vec::with_capacity(16);
+                for di in -1..=2 {
+                    let i = (i_base + di as f64).clamp(0.0, (self.height - 1) as f64) as usize;
+                    let wi = cubic_weight(di as f64 - fi);
+                    for dj in -1..=2 {
+                        let j = (j_base + dj as f64).clamp(0.0, (self.width - 1) as f64) as usize;
+                        let wj = cubic_weight(dj as f64 - fj);
+                        taps.push((i, j, wi * wj));
+                    }
+                }
+                self.weighted_sample(&taps, alpha_band, sample_bytes, out, out_offset);
+            }
+            ResampleAlg::Average { radius_x, radius_y } => {
+                let taps = box_average_taps(x, y, radius_x, radius_y, self.width, self.height);
+                self.weighted_sample(&taps, alpha_band, sample_bytes, out, out_offset);
+            }
+        }
+    }
+
+    /// Writes the weighted combination of `taps` (`(row, col, weight)`) into `out[out_offset..]`,
+    /// one band at a time, skipping/reweighting around nodata the same way for any neighborhood
+    /// size (4 taps for bilinear, 16 for cubic).
+    fn weighted_sample(
+        &self,
+        taps: &[(usize, usize, f64)],
+        alpha_band: Option<usize>,
+        sample_bytes: usize,
+        out: &mut [u8],
+        out_offset: usize,
+    ) {
+        let valid_weight: f64 = taps
+            .iter()
+            .filter(|(i, j, _)| self.is_valid(*i, *j, alpha_band, sample_bytes))
+            .map(|(_, _, w)| w)
+            .sum();
+        for band in 0..self.nbands {
+            let is_alpha = alpha_band == Some(band);
+            let denom = if is_alpha { 1.0 } else { valid_weight };
+            let value = if denom == 0.0 {
+                0.0
+            } else {
+                taps.iter()
+                    .filter(|(i, j, _)| is_alpha || self.is_valid(*i, *j, alpha_band, sample_bytes))
+                    .map(|(i, j, w)| {
+                        w * self.data_type.read_sample(
+                            &self.data,
+                            self.pixel_offset(*i, *j, sample_bytes) + band * sample_bytes,
+                        )
+                    })
+                    .sum::<f64>()
+                    / denom
+            };
+            self.data_type
+                .write_sample(out, out_offset + band * sample_bytes, value);
+        }
+    }
+
     pub fn drop_alpha(self) -> ImageBuffer {
         if self.has_alpha {
             let visual_bands = self.nbands - 1;
@@ -75,14 +595,244 @@ impl ImageBuffer {
                 nbands: visual_bands,
                 data_type: self.data_type,
                 has_alpha: false,
+                nodata: self.nodata,
                 data,
             }
         } else {
             self
         }
     }
+
+    /// Expands this single-band palette-index image into a 3-band RGB buffer by looking up each
+    /// pixel's entry in `color_map`, for `PhotometricInterpretation::Palette` (TIFF value 3)
+    /// images. Unlike `to_rgb`, there's no vmin/vmax normalization: the index value is an exact
+    /// lookup key, not a sample to be stretched.
+    ///
+    /// The index is always read from band 0. A 4th (alpha) band is emitted whenever this image
+    /// already carries one, or has a nodata sentinel, so masked/sparse pixels come out
+    /// transparent instead of whatever color the index happens to map to. `color_map` entries are
+    /// 8-bit, so the output always comes out as `DataType::Uint8` regardless of the index band's
+    /// own sample depth.
+    pub fn apply_palette(&self, color_map: &ColorMap) -> ImageBuffer {
+        let sample_bytes = self.data_type.size_bytes();
+        let self_alpha_band = if self.has_alpha {
+            Some(self.nbands - 1)
+        } else {
+            None
+        };
+        let has_alpha = self.has_alpha || self.nodata.is_some();
+        let out_nbands = if has_alpha { 4 } else { 3 };
+        let mut out_data = vec![0u8; self.width * self.height * out_nbands];
+        for i in 0..self.height {
+            for j in 0..self.width {
+                let idx = self
+                    .data_type
+                    .read_sample(&self.data, self.pixel_offset(i, j, sample_bytes))
+                    as usize;
+                let out_offset = i * self.width * out_nbands + j * out_nbands;
+                out_data[out_offset..out_offset + 3].copy_from_slice(&color_map.lookup(idx));
+                if has_alpha {
+                    out_data[out_offset + 3] =
+                        if self.is_valid(i, j, self_alpha_band, sample_bytes) {
+                            255
+                        } else {
+                            0
+                        };
+                }
+            }
+        }
+        ImageBuffer {
+            width: self.width,
+            height: self.height,
+            nbands: out_nbands,
+            has_alpha,
+            data_type: DataType::Uint8,
+            nodata: None,
+            data: out_data,
+        }
+    }
+
+    /// Flips every data-band sample (`value' = max_value - value`), for a
+    /// `PhotometricInterpretation::WhiteIsZero` (TIFF value 0) grayscale image, where - unlike the
+    /// far more common `BlackIsZero` - a sample of 0 means white rather than black. The alpha band
+    /// (if any) is left untouched.
+    pub fn invert_grayscale(&self) -> ImageBuffer {
+        let sample_bytes = self.data_type.size_bytes();
+        let max_value = self.data_type.max_value();
+        let data_bands = if self.has_alpha {
+            self.nbands - 1
+        } else {
+            self.nbands
+        };
+        let mut data = self.data.clone();
+        for i in 0..self.height {
+            for j in 0..self.width {
+                for band in 0..data_bands {
+                    let offset = self.pixel_offset(i, j, sample_bytes) + band * sample_bytes;
+                    let value = self.data_type.read_sample(&data, offset);
+                    self.data_type
+                        .write_sample(&mut data, offset, max_value - value);
+                }
+            }
+        }
+        ImageBuffer { data, ..self.clone() }
+    }
+
+    /// Converts a `PhotometricInterpretation::Separated` (CMYK, TIFF value 5) image to RGB using
+    /// the naive `R = max * (1 - C) * (1 - K)` formula (and likewise for G/B), ignoring any ICC
+    /// profile. Bands are assumed to be in C, M, Y, K order, the alpha band (if any) last. The
+    /// samples are first normalized against `DataType::max_value` so this works for any integer
+    /// sample depth, not just 8-bit - but the output is always re-quantized to `DataType::Uint8`,
+    /// never the input's own depth.
+    pub fn cmyk_to_rgb(&self) -> ImageBuffer {
+        let sample_bytes = self.data_type.size_bytes();
+        let max_value = self.data_type.max_value();
+        let self_alpha_band = if self.has_alpha {
+            Some(self.nbands - 1)
+        } else {
+            None
+        };
+        let has_alpha = self.has_alpha || self.nodata.is_some();
+        let out_nbands = if has_alpha { 4 } else { 3 };
+        let mut out_data = vec![0u8; self.width * self.height * out_nbands];
+        for i in 0..self.height {
+            for j in 0..self.width {
+                let pixel_offset = self.pixel_offset(i, j, sample_bytes);
+                let read = |band: usize| -> f64 {
+                    self.data_type
+                        .read_sample(&self.data, pixel_offset + band * sample_bytes)
+                        / max_value
+                };
+                let (c, m, y, k) = (read(0), read(1), read(2), read(3));
+                let out_offset = i * self.width * out_nbands + j * out_nbands;
+                out_data[out_offset] = (255.0 * (1.0 - c) * (1.0 - k)).round() as u8;
+                out_data[out_offset + 1] = (255.0 * (1.0 - m) * (1.0 - k)).round() as u8;
+                out_data[out_offset + 2] = (255.0 * (1.0 - y) * (1.0 - k)).round() as u8;
+                if has_alpha {
+                    out_data[out_offset + 3] =
+                        if self.is_valid(i, j, self_alpha_band, sample_bytes) {
+                            255
+                        } else {
+                            0
+                        };
+                }
+            }
+        }
+        ImageBuffer {
+            width: self.width,
+            height: self.height,
+            nbands: out_nbands,
+            has_alpha,
+            data_type: DataType::Uint8,
+            nodata: None,
+            data: out_data,
+        }
+    }
+
+    /// Converts a `PhotometricInterpretation::YCbCr` (TIFF value 6) image that wasn't JPEG-
+    /// compressed into RGB (a JPEG-compressed one already comes out of the JPEG decoder as RGB).
+    /// Bands are assumed Y, Cb, Cr, in that order, the alpha band (if any) last. `reference_black_
+    /// white` is the `ReferenceBlackWhite` tag value (or its spec default of `[0, 255, 128, 255,
+    /// 128, 255]`, which makes this step a no-op) used to scale each channel's code range before
+    /// the CCIR 601 inverse transform. Like `cmyk_to_rgb`, the output is always `DataType::Uint8`,
+    /// regardless of the input's own sample depth.
+    pub fn ycbcr_to_rgb(&self, reference_black_white: [f64; 6]) -> ImageBuffer {
+        let sample_bytes = self.data_type.size_bytes();
+        let self_alpha_band = if self.has_alpha {
+            Some(self.nbands - 1)
+        } else {
+            None
+        };
+        let has_alpha = self.has_alpha || self.nodata.is_some();
+        let out_nbands = if has_alpha { 4 } else { 3 };
+        let [y_black, y_white, cb_black, cb_white, cr_black, cr_white] = reference_black_white;
+        let mut out_data = vec![0u8; self.width * self.height * out_nbands];
+        for i in 0..self.height {
+            for j in 0..self.width {
+                let pixel_offset = self.pixel_offset(i, j, sample_bytes);
+                let read = |band: usize| {
+                    self.data_type
+                        .read_sample(&self.data, pixel_offset + band * sample_bytes)
+                };
+                let y = (read(0) - y_black) * 255.0 / (y_white - y_black);
+                let cb = (read(1) - cb_black) * 127.0 / (cb_white - cb_black);
+                let cr = (read(2) - cr_black) * 127.0 / (cr_white - cr_black);
+                let out_offset = i * self.width * out_nbands + j * out_nbands;
+                out_data[out_offset] = (y + 1.402 * cr).round().clamp(0.0, 255.0) as u8;
+                out_data[out_offset + 1] =
+                    (y - 0.344136 * cb - 0.714136 * cr).round().clamp(0.0, 255.0) as u8;
+                out_data[out_offset + 2] = (y + 1.772 * cb).round().clamp(0.0, 255.0) as u8;
+                if has_alpha {
+                    out_data[out_offset + 3] =
+                        if self.is_valid(i, j, self_alpha_band, sample_bytes) {
+                            255
+                        } else {
+                            0
+                        };
+                }
+            }
+        }
+        ImageBuffer {
+            width: self.width,
+            height: self.height,
+            nbands: out_nbands,
+            has_alpha,
+            data_type: DataType::Uint8,
+            nodata: None,
+            data: out_data,
+        }
+    }
+
+    /// Folds a per-pixel mask (as produced by the tiler's `TileData::mask` - 255=valid,
+    /// 0=transparent, one entry per pixel in row-major order) into this image as its alpha band,
+    /// ANDed with any alpha/nodata this image already carries. Lets a tile server turn
+    /// `extract_tile`'s `(img, mask)` pair into a single RGBA buffer so out-of-image and nodata
+    /// areas come out as transparent PNG rather than a black border.
+    pub fn apply_mask(&self, mask: &[u8]) -> ImageBuffer {
+        assert_eq!(mask.len(), self.width * self.height);
+        let sample_bytes = self.data_type.size_bytes();
+        let self_alpha_band = if self.has_alpha {
+            Some(self.nbands - 1)
+        } else {
+            None
+        };
+        let visual_bands = self_alpha_band.unwrap_or(self.nbands);
+        let out_nbands = visual_bands + 1;
+        let mut out_data = vec![0u8; self.width * self.height * out_nbands * sample_bytes];
+        for i in 0..self.height {
+            for j in 0..self.width {
+                let in_offset = self.pixel_offset(i, j, sample_bytes);
+                let out_offset = (i * self.width * out_nbands + j * out_nbands) * sample_bytes;
+                out_data[out_offset..out_offset + visual_bands * sample_bytes].copy_from_slice(
+                    &self.data[in_offset..in_offset + visual_bands * sample_bytes],
+                );
+                let valid = mask[i * self.width + j] != 0
+                    && self.is_valid(i, j, self_alpha_band, sample_bytes);
+                self.data_type.write_sample(
+                    &mut out_data,
+                    out_offset + visual_bands * sample_bytes,
+                    if valid { 255.0 } else { 0.0 },
+                );
+            }
+        }
+        ImageBuffer {
+            width: self.width,
+            height: self.height,
+            nbands: out_nbands,
+            has_alpha: true,
+            data_type: self.data_type,
+            nodata: None,
+            data: out_data,
+        }
+    }
 }
 
+/// Concatenates `image1`'s and `image2`'s bands into a single image, e.g. to append a
+/// single-band mask read from a separate mask overview onto an RGB image. Either (or both) input
+/// may itself carry an alpha band: each image's own alpha is stripped from the middle of the
+/// concatenation and folded into one combined alpha band at the end (valid only where both
+/// inputs are valid), rather than rejecting the combination or leaving stray alpha bands in the
+/// visual data.
 pub fn stack(image1: &ImageBuffer, image2: &ImageBuffer) -> Result<ImageBuffer, Error> {
     if image1.width != image2.width || image1.height != image2.height {
         return Err(Error::OtherError(format!(
@@ -96,39 +846,66 @@ pub fn stack(image1: &ImageBuffer, image2: &ImageBuffer) -> Result<ImageBuffer,
             image1.data_type, image2.data_type,
         )));
     }
-    if image1.has_alpha {
-        return Err(Error::OtherError(
-            "doesn't support stacking first image with alpha".to_string(),
-        ));
-    }
-    let mut out_data = vec![
-        0u8;
-        image1.width
-            * image1.height
-            * (image1.nbands + image2.nbands)
-            * image1.data_type.size_bytes()
-    ];
+    let sample_bytes = image1.data_type.size_bytes();
+    let visual1 = if image1.has_alpha {
+        image1.nbands - 1
+    } else {
+        image1.nbands
+    };
+    let visual2 = if image2.has_alpha {
+        image2.nbands - 1
+    } else {
+        image2.nbands
+    };
+    let has_alpha = image1.has_alpha || image2.has_alpha;
+    let out_nbands = visual1 + visual2 + if has_alpha { 1 } else { 0 };
+
+    let mut out_data = vec![0u8; image1.width * image1.height * out_nbands * sample_bytes];
     for i in 0..image1.height {
         for j in 0..image1.width {
-            let out_offset = i * image1.width * (image1.nbands + image2.nbands)
-                + j * (image1.nbands + image2.nbands);
-            out_data[out_offset..out_offset + image1.nbands].copy_from_slice({
-                let in_offset = i * image1.width * image1.nbands + j * image1.nbands;
-                &image1.data[in_offset..in_offset + image1.nbands]
-            });
-            out_data[out_offset + image1.nbands..out_offset + image1.nbands + image2.nbands]
-                .copy_from_slice({
-                    let in_offset = i * image2.width * image2.nbands + j * image2.nbands;
-                    &image2.data[in_offset..in_offset + image2.nbands]
-                });
+            let out_offset = (i * image1.width * out_nbands + j * out_nbands) * sample_bytes;
+            let in1_offset = image1.pixel_offset(i, j, sample_bytes);
+            let in2_offset = image2.pixel_offset(i, j, sample_bytes);
+            out_data[out_offset..out_offset + visual1 * sample_bytes]
+                .copy_from_slice(&image1.data[in1_offset..in1_offset + visual1 * sample_bytes]);
+            out_data[out_offset + visual1 * sample_bytes
+                ..out_offset + (visual1 + visual2) * sample_bytes]
+                .copy_from_slice(&image2.data[in2_offset..in2_offset + visual2 * sample_bytes]);
+            if has_alpha {
+                let alpha_offset = out_offset + (visual1 + visual2) * sample_bytes;
+                let combined = match (image1.has_alpha, image2.has_alpha) {
+                    (true, true) => {
+                        let a1 = image1
+                            .data_type
+                            .read_sample(&image1.data, in1_offset + visual1 * sample_bytes);
+                        let a2 = image2
+                            .data_type
+                            .read_sample(&image2.data, in2_offset + visual2 * sample_bytes);
+                        a1.min(a2)
+                    }
+                    (true, false) => image1
+                        .data_type
+                        .read_sample(&image1.data, in1_offset + visual1 * sample_bytes),
+                    (false, true) => image2
+                        .data_type
+                        .read_sample(&image2.data, in2_offset + visual2 * sample_bytes),
+                    (false, false) => unreachable!("has_alpha implies one side has alpha"),
+                };
+                image1.data_type.write_sample(&mut out_data, alpha_offset, combined);
+            }
         }
     }
     Ok(ImageBuffer {
         width: image1.width,
-        height: image2.height,
-        nbands: image1.nbands + image2.nbands,
-        has_alpha: image2.has_alpha,
+        height: image1.height,
+        nbands: out_nbands,
+        has_alpha,
         data_type: image1.data_type,
+        nodata: if image1.nodata == image2.nodata {
+            image1.nodata
+        } else {
+            None
+        },
         data: out_data,
     })
 }
@@ -137,6 +914,8 @@ pub fn stack(image1: &ImageBuffer, image2: &ImageBuffer) -> Result<ImageBuffer,
 mod tests {
     use super::DataType;
     use super::ImageBuffer;
+    use super::ResampleAlg;
+    use super::DEFAULT_STRETCH_LOW_PERCENTILE;
     use super::{drop_alpha, stack};
 
     #[test]
@@ -155,6 +934,7 @@ mod tests {
                 nbands: 3,
                 has_alpha: false,
                 data_type: DataType::Uint8,
+                nodata: None,
                 data,
             }
         };
@@ -168,6 +948,7 @@ mod tests {
                 nbands: 1,
                 has_alpha: true,
                 data_type: DataType::Uint8,
+                nodata: None,
                 data,
             }
         };
@@ -190,6 +971,41 @@ mod tests {
         assert_eq!(res.data[4 * width * 4 + 10 * 4 + 3], 7);
     }
 
+    #[test]
+    fn test_stack_combines_masks_from_both_images() {
+        // Both images carry their own alpha band; the stacked result should only be valid where
+        // both inputs are, instead of rejecting the first image for having alpha at all.
+        let width = 2;
+        let height = 1;
+        let image1 = ImageBuffer {
+            width,
+            height,
+            nbands: 2,
+            has_alpha: true,
+            data_type: DataType::Uint8,
+            nodata: None,
+            // (value=10, alpha=255), (value=20, alpha=0)
+            data: vec![10, 255, 20, 0],
+        };
+        let image2 = ImageBuffer {
+            width,
+            height,
+            nbands: 2,
+            has_alpha: true,
+            data_type: DataType::Uint8,
+            nodata: None,
+            // (value=1, alpha=0), (value=2, alpha=255)
+            data: vec![1, 0, 2, 255],
+        };
+        let res = stack(&image1, &image2).unwrap();
+        assert_eq!(res.nbands, 3);
+        assert!(res.has_alpha);
+        // pixel 0: image1 valid, image2 invalid => combined invalid
+        assert_eq!(res.data[0..3], [10, 1, 0]);
+        // pixel 1: image1 invalid, image2 valid => combined invalid
+        assert_eq!(res.data[3..6], [20, 2, 0]);
+    }
+
     #[test]
     fn test_to_rgb() {
         let image1 = {
@@ -208,6 +1024,7 @@ mod tests {
                 nbands,
                 has_alpha: false,
                 data_type: DataType::Float32,
+                nodata: None,
                 data,
             }
         };
@@ -223,6 +1040,55 @@ mod tests {
         assert_eq!(res.data[offset..offset + 3], [0, 127, 0]);
     }
 
+    #[test]
+    fn test_to_rgb_auto_stretch() {
+        // 1 band, values 0..=100 laid out across the row, so the 2nd/98th percentiles land close
+        // to 2 and 98.
+        let width = 101;
+        let height = 1;
+        let data: Vec<u8> = (0..=100u8).collect();
+        let image1 = ImageBuffer {
+            width,
+            height,
+            nbands: 1,
+            has_alpha: false,
+            data_type: DataType::Uint8,
+            nodata: None,
+            data,
+        };
+        let (res, bounds) = image1
+            .to_rgb_auto_stretch(&[0, 0, 0], DEFAULT_STRETCH_LOW_PERCENTILE, 98.0)
+            .unwrap();
+        assert_eq!(res.width, 101);
+        assert_eq!(res.height, 1);
+        assert_eq!(res.nbands, 3);
+        for (vmin, vmax) in bounds {
+            assert!((vmin - 2.0).abs() < 0.5, "vmin={}", vmin);
+            assert!((vmax - 98.0).abs() < 0.5, "vmax={}", vmax);
+        }
+    }
+
+    #[test]
+    fn test_to_rgb_nodata_becomes_transparent() {
+        // 1 band, with pixel (x=0, y=0) set to the nodata sentinel
+        let image1 = ImageBuffer {
+            width: 2,
+            height: 1,
+            nbands: 1,
+            has_alpha: false,
+            data_type: DataType::Uint8,
+            nodata: Some(0.0),
+            data: vec![0, 42],
+        };
+        let res = image1.to_rgb(&[0, 0, 0], 0.0, 100.0).unwrap();
+        assert_eq!(res.nbands, 4);
+        assert!(res.has_alpha);
+        // nodata pixel => transparent
+        assert_eq!(res.data[3], 0);
+        // regular pixel => opaque
+        assert_eq!(res.data[7], 255);
+    }
+
     #[test]
     fn test_drop_alpha() {
         let width = 32;
@@ -241,6 +1107,7 @@ mod tests {
                 nbands: 4,
                 has_alpha: true,
                 data_type: DataType::Uint8,
+                nodata: None,
                 data,
             }
         };
@@ -259,4 +1126,264 @@ mod tests {
             assert_eq!(res.data[offset..offset + 3], [0, 0, 56]);
         }
     }
+
+    #[test]
+    fn test_apply_palette() {
+        use crate::tiff::tags::ColorMap;
+
+        let width = 3;
+        let height = 1;
+        // index 0 -> red, index 1 -> green, index 2 is unused/invalid (nodata)
+        let color_map = ColorMap {
+            red: vec![65535, 0, 0],
+            green: vec![0, 65535, 0],
+            blue: vec![0, 0, 0],
+        };
+        let image1 = ImageBuffer {
+            width,
+            height,
+            nbands: 1,
+            has_alpha: false,
+            data_type: DataType::Uint8,
+            nodata: Some(2.0),
+            data: vec![0, 1, 2],
+        };
+        let res = image1.apply_palette(&color_map);
+        assert_eq!(res.nbands, 4);
+        assert!(res.has_alpha);
+        assert_eq!(res.data_type, DataType::Uint8);
+        assert_eq!(res.data[0..4], [255, 0, 0, 255]);
+        assert_eq!(res.data[4..8], [0, 255, 0, 255]);
+        // index 2 is the nodata sentinel, so its color lookup is irrelevant; it must come out
+        // transparent
+        assert_eq!(res.data[8 + 3], 0);
+    }
+
+    #[test]
+    fn test_invert_grayscale() {
+        // WhiteIsZero: 0 is white, 255 is black, so inverting should flip the two
+        let image1 = ImageBuffer {
+            width: 2,
+            height: 1,
+            nbands: 1,
+            has_alpha: false,
+            data_type: DataType::Uint8,
+            nodata: None,
+            data: vec![0, 255],
+        };
+        let res = image1.invert_grayscale();
+        assert_eq!(res.nbands, 1);
+        assert_eq!(res.data_type, DataType::Uint8);
+        assert_eq!(res.data, vec![255, 0]);
+    }
+
+    #[test]
+    fn test_cmyk_to_rgb() {
+        // pure cyan (C=max, M=Y=K=0) should come out as (0, 255, 255)
+        let image1 = ImageBuffer {
+            width: 1,
+            height: 1,
+            nbands: 4,
+            has_alpha: false,
+            data_type: DataType::Uint8,
+            nodata: None,
+            data: vec![255, 0, 0, 0],
+        };
+        let res = image1.cmyk_to_rgb();
+        assert_eq!(res.nbands, 3);
+        assert_eq!(res.data_type, DataType::Uint8);
+        assert_eq!(res.data, vec![0, 255, 255]);
+    }
+
+    #[test]
+    fn test_ycbcr_to_rgb() {
+        // Default ReferenceBlackWhite, so this step is a no-op and the values are a plain CCIR 601
+        // inverse transform: mid-gray (Y=128, Cb=Cr=128) should round-trip to itself.
+        let reference_black_white = [0.0, 255.0, 128.0, 255.0, 128.0, 255.0];
+        let image1 = ImageBuffer {
+            width: 1,
+            height: 1,
+            nbands: 3,
+            has_alpha: false,
+            data_type: DataType::Uint8,
+            nodata: None,
+            data: vec![128, 128, 128],
+        };
+        let res = image1.ycbcr_to_rgb(reference_black_white);
+        assert_eq!(res.nbands, 3);
+        assert_eq!(res.data_type, DataType::Uint8);
+        assert_eq!(res.data, vec![128, 128, 128]);
+    }
+
+    #[test]
+    fn test_apply_mask() {
+        let width = 3;
+        let height = 1;
+        // pixel 1 is the nodata sentinel, so it should come out transparent even though its mask
+        // entry says valid
+        let image = ImageBuffer {
+            width,
+            height,
+            nbands: 3,
+            has_alpha: false,
+            data_type: DataType::Uint8,
+            nodata: Some(0.0),
+            data: vec![10, 20, 30, 0, 0, 0, 40, 50, 60],
+        };
+        let mask = vec![255, 255, 0];
+        let res = image.apply_mask(&mask);
+        assert_eq!(res.nbands, 4);
+        assert!(res.has_alpha);
+        assert_eq!(res.data[0..4], [10, 20, 30, 255]);
+        assert_eq!(res.data[4..8], [0, 0, 0, 0]);
+        assert_eq!(res.data[8..12], [40, 50, 60, 0]);
+    }
+
+    #[test]
+    fn test_resample_to_nearest_downsamples() {
+        // A 4x4 single-band checkerboard, downsampled 2x with nearest neighbor
+        let image = ImageBuffer {
+            width: 4,
+            height: 4,
+            nbands: 1,
+            has_alpha: false,
+            data_type: DataType::Uint8,
+            nodata: None,
+            data: vec![
+                10, 10, 20, 20, //
+                10, 10, 20, 20, //
+                30, 30, 40, 40, //
+                30, 30, 40, 40, //
+            ],
+        };
+        let res = image.resample_to(2, 2, ResampleAlg::Nearest);
+        assert_eq!(res.width, 2);
+        assert_eq!(res.height, 2);
+        assert_eq!(res.data, vec![10, 20, 30, 40]);
+    }
+
+    #[test]
+    fn test_resample_to_average_downsamples() {
+        // Same 4x4 checkerboard as the nearest-neighbor test, but each downsampled output pixel
+        // should now be the average of its 2x2 source block rather than a single sample of it.
+        let image = ImageBuffer {
+            width: 4,
+            height: 4,
+            nbands: 1,
+            has_alpha: false,
+            data_type: DataType::Uint8,
+            nodata: None,
+            data: vec![
+                10, 10, 20, 20, //
+                10, 14, 20, 20, //
+                30, 30, 40, 40, //
+                30, 30, 40, 44, //
+            ],
+        };
+        // The radius fields are ignored by resample_to, which derives its own box from the
+        // scale factor - pass nonsense values to make sure of that.
+        let res = image.resample_to(
+            2,
+            2,
+            ResampleAlg::Average {
+                radius_x: 0.0,
+                radius_y: 0.0,
+            },
+        );
+        assert_eq!(res.width, 2);
+        assert_eq!(res.height, 2);
+        assert_eq!(res.data, vec![11, 20, 30, 41]);
+    }
+
+    #[test]
+    fn test_resample_to_bilinear_upsamples() {
+        // A 2x2 single-band image, upsampled 2x with bilinear: the center pixels should be the
+        // average of all 4 source pixels
+        let image = ImageBuffer {
+            width: 2,
+            height: 2,
+            nbands: 1,
+            has_alpha: false,
+            data_type: DataType::Uint8,
+            nodata: None,
+            data: vec![0, 100, 200, 100],
+        };
+        let res = image.resample_to(4, 4, ResampleAlg::Bilinear);
+        assert_eq!(res.width, 4);
+        assert_eq!(res.height, 4);
+        // Corner pixels should match the nearest source corner (clamped sampling at the edges)
+        assert_eq!(res.data[0], 0);
+        assert_eq!(res.data[3], 100);
+        assert_eq!(res.data[12], 200);
+        assert_eq!(res.data[15], 100);
+    }
+
+    #[test]
+    fn test_resample_to_bilinear_skips_transparent_neighbors() {
+        // A 2x1 RGBA image where the right pixel is fully transparent (nodata). A destination
+        // pixel straddling the two should get its color entirely from the valid left pixel
+        // instead of being diluted towards the nodata pixel's (unset) color, while alpha still
+        // fades smoothly towards transparent.
+        let image = ImageBuffer {
+            width: 2,
+            height: 1,
+            nbands: 2,
+            has_alpha: true,
+            data_type: DataType::Uint8,
+            nodata: None,
+            // (value=200, alpha=255), (value=0, alpha=0)
+            data: vec![200, 255, 0, 0],
+        };
+        let res = image.resample_to(4, 1, ResampleAlg::Bilinear);
+        // dst_j=2 samples 75% of the way from the left (valid) to the right (transparent) pixel
+        let offset = 2 * res.nbands;
+        assert_eq!(res.data[offset], 200);
+        assert_eq!(res.data[offset + 1], 64);
+    }
+
+    #[test]
+    fn test_resample_to_cubic_upsamples() {
+        // A flat 4x4 image should resample to the same flat value everywhere with cubic, same as
+        // it would with nearest/bilinear - this mostly checks the 4x4 neighborhood/edge clamping
+        // doesn't blow up or introduce ringing on a constant signal
+        let image = ImageBuffer {
+            width: 4,
+            height: 4,
+            nbands: 1,
+            has_alpha: false,
+            data_type: DataType::Uint8,
+            nodata: None,
+            data: vec![42; 16],
+        };
+        let res = image.resample_to(8, 8, ResampleAlg::Cubic);
+        assert_eq!(res.data, vec![42; 64]);
+    }
+
+    #[test]
+    fn test_sample_at_matches_source_pixel_for_all_algs() {
+        // Sampling exactly at a pixel's corner-aligned coordinate should return that pixel's
+        // value for every resampling kernel
+        let image = ImageBuffer {
+            width: 3,
+            height: 3,
+            nbands: 1,
+            has_alpha: false,
+            data_type: DataType::Uint8,
+            nodata: None,
+            data: vec![
+                10, 20, 30, //
+                40, 50, 60, //
+                70, 80, 90, //
+            ],
+        };
+        for alg in [
+            ResampleAlg::Nearest,
+            ResampleAlg::Bilinear,
+            ResampleAlg::Cubic,
+        ] {
+            let mut out = vec![0u8; 1];
+            image.sample_at(1.5, 1.5, alg, &mut out, 0);
+            assert_eq!(out[0], 50, "{:?}", alg);
+        }
+    }
 }
@@ -1,19 +1,25 @@
 mod bbox;
+pub mod bmp;
 mod epsg;
 mod errors;
 mod hex;
 pub mod image;
 mod math;
+pub mod pmtiles;
 pub mod ppm;
 mod sources;
 mod tiff;
 pub mod tiler;
 
 pub use bbox::BoundingBox;
+pub use epsg::{Crs, UnitOfMeasure};
 pub use errors::Error;
+pub use sources::{AsyncSourceReader, DiskCacheConfig};
 pub use tiff::cog::{ImageRect, COG};
 pub use tiff::data_types::DataType;
+pub use tiff::georef::{Georeference, Geotransform};
 pub use tiff::ifd::{FullyDecodedIFDEntry, TIFFReader};
+pub use tiff::writer::WriteOptions;
 
 pub async fn open(source_spec: &str) -> Result<COG, Error> {
     COG::open(source_spec).await
@@ -1,29 +1,44 @@
+use crate::image::{DataType, ImageBuffer};
 use std::io::{self, Write};
 
 /// Small utility functions to write .npy files. Handy to debug things using python
 
-pub fn write_to_npy(
-    filename: &str,
-    img_data: Vec<u8>,
-    img_shape: [usize; 3],
-) -> Result<(), io::Error> {
+/// The numpy `descr` string for `dtype`'s in-memory layout - see `DataType::read_sample`'s doc
+/// comment for why this is always little-endian (`'|'`, numpy's "byte order doesn't matter" marker,
+/// for the single-byte `Uint8` case).
+fn npy_descr(dtype: DataType) -> &'static str {
+    match dtype {
+        DataType::Uint8 => "'|u1'",
+        DataType::Int16 => "'<i2'",
+        DataType::Uint16 => "'<u2'",
+        DataType::Int32 => "'<i4'",
+        DataType::Uint32 => "'<u4'",
+        DataType::Float32 => "'<f4'",
+        DataType::Float64 => "'<f8'",
+    }
+}
+
+/// Writes `img` out as a `.npy` file, with `dtype` and `shape` (height, width, nbands) taken from
+/// `img` itself rather than assumed by the caller.
+pub fn write_to_npy(filename: &str, img: &ImageBuffer) -> Result<(), io::Error> {
     let mut file = std::fs::File::create(filename)?;
-    let dtype = "'uint8'";
     let dict = format!(
         "{{\"descr\": {}, \"fortran_order\": False, \"shape\": ({}, {}, {})}}\n",
-        dtype, img_shape[0], img_shape[1], img_shape[2]
+        npy_descr(img.data_type),
+        img.height,
+        img.width,
+        img.nbands
     );
     let dict_bytes = dict.as_bytes();
     let magic = [0x93u8, b'N', b'U', b'M', b'P', b'Y', 0x01, 0x00];
     let size = magic.len() + 2 + dict_bytes.len();
     let padding = 64 * ((size + 63) / 64) - size;
-    println!("padding with {} {}", padding, padding + size);
     let header_len = (dict_bytes.len() as u16 + padding as u16).to_le_bytes();
     file.write_all(&magic)?;
     file.write_all(&header_len)?;
     file.write_all(dict_bytes)?;
     file.write_all(&vec![0x20; padding])?;
-    file.write_all(&img_data)?;
+    file.write_all(&img.data)?;
     file.flush()?;
     Ok(())
 }
@@ -0,0 +1,382 @@
+/// Packs a pyramid of already-encoded raster tiles (e.g. the PNG/JPEG bytes a caller produced
+/// from `tiler::extract_tile`'s output) into a single PMTiles v3 archive, so a COG's tiles can be
+/// served as a static, range-readable file without a running tile server.
+///
+/// See the spec: https://github.com/protomaps/PMTiles/blob/main/spec/v3/spec.md
+///
+/// This only writes a single, flat root directory - large pyramids whose directory would exceed
+/// what's practical to keep in memory on the reading side need leaf directories, which aren't
+/// implemented yet.
+use crate::bbox::BoundingBox;
+use crate::image::ImageBuffer;
+use crate::tiler::cache::xyz_tile_range;
+use crate::tiler::{extract_tile, TMSTileCoords};
+use crate::{Error, COG};
+use flate2::write::GzEncoder;
+use proj::Transform;
+use std::collections::HashMap;
+use std::io::Write;
+
+pub const HEADER_LEN: usize = 127;
+const MAGIC: &[u8; 7] = b"PMTiles";
+const VERSION: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Unknown = 0,
+    None = 1,
+    Gzip = 2,
+    Brotli = 3,
+    Zstd = 4,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TileType {
+    Unknown = 0,
+    Mvt = 1,
+    Png = 2,
+    Jpeg = 3,
+    Webp = 4,
+    Avif = 5,
+}
+
+/// Returns the Hilbert curve distance of `(x, y)` within an `n x n` grid (`n` a power of 2), per
+/// the standard xy2d algorithm (https://en.wikipedia.org/wiki/Hilbert_curve#Applications_and_mapping_algorithms).
+fn hilbert_xy2d(n: u64, mut x: u64, mut y: u64) -> u64 {
+    let mut d: u64 = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = u64::from((x & s) > 0);
+        let ry = u64::from((y & s) > 0);
+        d += s * s * ((3 * rx) ^ ry);
+        // Rotate the quadrant
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    d
+}
+
+/// Number of tiles in zoom levels `0..z`, i.e. `(4^z - 1) / 3`.
+fn tiles_before_zoom(z: u8) -> u64 {
+    (4u64.pow(z as u32) - 1) / 3
+}
+
+/// Computes a tile's PMTiles id, addressing tiles along a Hilbert curve within their zoom level.
+/// `x`/`y` follow the usual XYZ "slippy map" convention (y increasing south), as PMTiles expects -
+/// `TMSTileCoords` uses the opposite (y increasing north) convention internally, so callers must
+/// flip `y` (`(1 << z) - 1 - y`) before calling this with TMS coordinates.
+fn tile_id(z: u8, x: u64, y: u64) -> u64 {
+    tiles_before_zoom(z) + hilbert_xy2d(1u64 << z, x, y)
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value != 0 {
+            buf.push(byte | 0x80);
+        } else {
+            buf.push(byte);
+            break;
+        }
+    }
+}
+
+// One run of consecutive tile ids sharing the same (offset, length) in the tile data section -
+// `run_length > 1` only when adjacent tile ids were deduplicated down to an identical blob.
+struct DirEntry {
+    tile_id: u64,
+    offset: u64,
+    length: u64,
+    run_length: u64,
+}
+
+// Serializes directory entries following the spec's column-oriented varint layout: entry count,
+// then all tile_id deltas, then all run_lengths, then all lengths, then all offsets (an offset
+// equal to the previous entry's `offset + length` - i.e. contiguous with it in the tile data
+// section - is stored as 0 to save space).
+fn serialize_directory(entries: &[DirEntry]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_varint(&mut buf, entries.len() as u64);
+    let mut prev_id = 0u64;
+    for e in entries {
+        write_varint(&mut buf, e.tile_id - prev_id);
+        prev_id = e.tile_id;
+    }
+    for e in entries {
+        write_varint(&mut buf, e.run_length);
+    }
+    for e in entries {
+        write_varint(&mut buf, e.length);
+    }
+    let mut prev_end: Option<u64> = None;
+    for e in entries {
+        if Some(e.offset) == prev_end {
+            write_varint(&mut buf, 0);
+        } else {
+            write_varint(&mut buf, e.offset + 1);
+        }
+        prev_end = Some(e.offset + e.length);
+    }
+    buf
+}
+
+fn gzip(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish().map_err(|e| e.into())
+}
+
+/// Builds a single PMTiles v3 archive out of tiles added via `add_tile`.
+#[derive(Default)]
+pub struct PMTilesWriter {
+    // (z, x, y, data), with x/y in this crate's TMS convention (y increasing north)
+    tiles: Vec<(u8, u64, u64, Vec<u8>)>,
+}
+
+impl PMTilesWriter {
+    pub fn new() -> PMTilesWriter {
+        PMTilesWriter::default()
+    }
+
+    /// Queues a tile's already-encoded bytes (e.g. PNG/JPEG) for inclusion in the archive.
+    pub fn add_tile(&mut self, coords: TMSTileCoords, data: Vec<u8>) {
+        self.tiles.push((coords.z as u8, coords.x, coords.y, data));
+    }
+
+    pub fn write_to_file(&self, filename: &str, tile_type: TileType) -> Result<(), Error> {
+        let mut file = std::fs::File::create(filename)?;
+        self.write(&mut file, tile_type)
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W, tile_type: TileType) -> Result<(), Error> {
+        if self.tiles.is_empty() {
+            return Err(Error::OtherError(
+                "Cannot write a PMTiles archive with no tiles".to_string(),
+            ));
+        }
+
+        // Address every tile along its zoom level's Hilbert curve, converting from this crate's
+        // TMS (y increasing north) convention to PMTiles' XYZ (y increasing south) one.
+        let mut addressed: Vec<(u64, &Vec<u8>)> = self
+            .tiles
+            .iter()
+            .map(|(z, x, y, data)| {
+                let xyz_y = (1u64 << z) - 1 - y;
+                (tile_id(*z, *x, xyz_y), data)
+            })
+            .collect();
+        addressed.sort_by_key(|(id, _)| *id);
+
+        // Deduplicate identical tile blobs: each unique blob is written once to the tile data
+        // section, and every tile referencing it points at the same (offset, length).
+        let mut tile_data = Vec::new();
+        let mut blob_ranges: HashMap<&[u8], (u64, u64)> = HashMap::new();
+        let mut entries: Vec<DirEntry> = Vec::with_capacity(addressed.len());
+        for (id, data) in &addressed {
+            let (offset, length) = *blob_ranges.entry(data.as_slice()).or_insert_with(|| {
+                let offset = tile_data.len() as u64;
+                tile_data.extend_from_slice(data);
+                (offset, data.len() as u64)
+            });
+            match entries.last_mut() {
+                Some(prev)
+                    if prev.tile_id + prev.run_length == *id
+                        && prev.offset == offset
+                        && prev.length == length =>
+                {
+                    prev.run_length += 1;
+                }
+                _ => entries.push(DirEntry {
+                    tile_id: *id,
+                    offset,
+                    length,
+                    run_length: 1,
+                }),
+            }
+        }
+
+        let min_zoom = self.tiles.iter().map(|(z, ..)| *z).min().unwrap();
+        let max_zoom = self.tiles.iter().map(|(z, ..)| *z).max().unwrap();
+        let (bounds, center) = self.lnglat_bounds_and_center()?;
+
+        let root_directory = gzip(&serialize_directory(&entries))?;
+        let json_metadata = gzip(b"{}")?;
+
+        let root_directory_offset = HEADER_LEN as u64;
+        let json_metadata_offset = root_directory_offset + root_directory.len() as u64;
+        // No leaf directories: all entries live in the root directory.
+        let leaf_directory_offset = json_metadata_offset + json_metadata.len() as u64;
+        let leaf_directory_length = 0u64;
+        let tile_data_offset = leaf_directory_offset + leaf_directory_length;
+
+        let addressed_tiles_count = entries.iter().map(|e| e.run_length).sum::<u64>();
+        let tile_entries_count = entries.len() as u64;
+        let tile_contents_count = blob_ranges.len() as u64;
+
+        let mut header = [0u8; HEADER_LEN];
+        header[0..7].copy_from_slice(MAGIC);
+        header[7] = VERSION;
+        header[8..16].copy_from_slice(&root_directory_offset.to_le_bytes());
+        header[16..24].copy_from_slice(&(root_directory.len() as u64).to_le_bytes());
+        header[24..32].copy_from_slice(&json_metadata_offset.to_le_bytes());
+        header[32..40].copy_from_slice(&(json_metadata.len() as u64).to_le_bytes());
+        header[40..48].copy_from_slice(&leaf_directory_offset.to_le_bytes());
+        header[48..56].copy_from_slice(&leaf_directory_length.to_le_bytes());
+        header[56..64].copy_from_slice(&tile_data_offset.to_le_bytes());
+        header[64..72].copy_from_slice(&(tile_data.len() as u64).to_le_bytes());
+        header[72..80].copy_from_slice(&addressed_tiles_count.to_le_bytes());
+        header[80..88].copy_from_slice(&tile_entries_count.to_le_bytes());
+        header[88..96].copy_from_slice(&tile_contents_count.to_le_bytes());
+        // Clustered: tile data is ordered by ascending tile id, which is true here since we built
+        // `tile_data` by iterating `addressed` in that order.
+        header[96] = 1;
+        header[97] = Compression::Gzip as u8;
+        header[98] = Compression::Unknown as u8;
+        header[99] = tile_type as u8;
+        header[100] = min_zoom;
+        header[101] = max_zoom;
+        header[102..106].copy_from_slice(&to_e7(bounds.xmin).to_le_bytes());
+        header[106..110].copy_from_slice(&to_e7(bounds.ymin).to_le_bytes());
+        header[110..114].copy_from_slice(&to_e7(bounds.xmax).to_le_bytes());
+        header[114..118].copy_from_slice(&to_e7(bounds.ymax).to_le_bytes());
+        header[118] = min_zoom;
+        header[119..123].copy_from_slice(&to_e7(center.0).to_le_bytes());
+        header[123..127].copy_from_slice(&to_e7(center.1).to_le_bytes());
+
+        writer.write_all(&header)?;
+        writer.write_all(&root_directory)?;
+        writer.write_all(&json_metadata)?;
+        writer.write_all(&tile_data)?;
+        Ok(())
+    }
+
+    // Reprojects the bounding box of all queued tiles (in their native EPSG:3857 tiling grid) to
+    // lon/lat, following the same pattern as `COG::lnglat_bounds`.
+    fn lnglat_bounds_and_center(&self) -> Result<(BoundingBox, (f64, f64)), Error> {
+        // Tiles aren't necessarily all at the same zoom, so go through 3857 meters (constant
+        // across zoom levels for a given tile's edges) rather than mixing tile indices directly.
+        let (meters_xmin, meters_ymin, meters_xmax, meters_ymax) = self
+            .tiles
+            .iter()
+            .fold(None, |acc: Option<(f64, f64, f64, f64)>, (z, x, y, _)| {
+                let (x0, y0) = crate::tiler::pixel_to_meters(
+                    (*x * crate::tiler::TILE_SIZE) as f64,
+                    (*y * crate::tiler::TILE_SIZE) as f64,
+                    *z as u32,
+                );
+                let (x1, y1) = crate::tiler::pixel_to_meters(
+                    ((*x + 1) * crate::tiler::TILE_SIZE) as f64,
+                    ((*y + 1) * crate::tiler::TILE_SIZE) as f64,
+                    *z as u32,
+                );
+                let (xmin, xmax) = (x0.min(x1), x0.max(x1));
+                let (ymin, ymax) = (y0.min(y1), y0.max(y1));
+                Some(match acc {
+                    None => (xmin, ymin, xmax, ymax),
+                    Some((a_xmin, a_ymin, a_xmax, a_ymax)) => (
+                        a_xmin.min(xmin),
+                        a_ymin.min(ymin),
+                        a_xmax.max(xmax),
+                        a_ymax.max(ymax),
+                    ),
+                })
+            })
+            .expect("already checked tiles is non-empty");
+        let transform = Transform::new(3857, 4326)?;
+        let lnglat = transform.transform_bounds(&proj::MinMaxes {
+            xmin: meters_xmin,
+            xmax: meters_xmax,
+            ymin: meters_ymin,
+            ymax: meters_ymax,
+        })?;
+        let bounds = BoundingBox {
+            xmin: lnglat.xmin,
+            xmax: lnglat.xmax,
+            ymin: lnglat.ymin,
+            ymax: lnglat.ymax,
+        };
+        let center = (
+            (bounds.xmin + bounds.xmax) / 2.0,
+            (bounds.ymin + bounds.ymax) / 2.0,
+        );
+        Ok((bounds, center))
+    }
+}
+
+fn to_e7(degrees: f64) -> i32 {
+    (degrees * 1e7) as i32
+}
+
+/// Renders `cog`'s tile pyramid across `min_zoom..=max_zoom` into a single PMTiles archive at
+/// `path`, skipping tiles outside `cog`'s footprint the same way `tiler::cache::warm` does -
+/// reprojecting `lnglat_bounds` to a per-zoom XYZ tile range rather than rendering every tile of
+/// every zoom level.
+pub async fn export_pyramid(
+    cog: &mut COG,
+    path: &str,
+    min_zoom: u32,
+    max_zoom: u32,
+    tile_type: TileType,
+    encode: impl Fn(&ImageBuffer) -> Result<Vec<u8>, Error>,
+) -> Result<(), Error> {
+    let bounds = cog.lnglat_bounds()?;
+    let mut writer = PMTilesWriter::new();
+    for z in min_zoom..=max_zoom {
+        let (x_from, x_to, y_from, y_to) = xyz_tile_range(&bounds, z);
+        for x in x_from..=x_to {
+            for y in y_from..=y_to {
+                let coords = TMSTileCoords::from_zxy(z, x, y);
+                let tile = extract_tile(cog, coords).await?;
+                writer.add_tile(coords, encode(&tile.img)?);
+            }
+        }
+    }
+    writer.write_to_file(path, tile_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_id_matches_known_values() {
+        // z=0 has a single tile, at the start of the curve
+        assert_eq!(tile_id(0, 0, 0), 0);
+        // First tile of z=1 continues right after all of z=0's tiles
+        assert_eq!(tile_id(1, 0, 0), 1);
+    }
+
+    #[test]
+    fn test_write_produces_well_formed_header() {
+        let mut writer = PMTilesWriter::new();
+        writer.add_tile(TMSTileCoords::from_zxy(1, 0, 0), vec![1, 2, 3]);
+        writer.add_tile(TMSTileCoords::from_zxy(1, 1, 0), vec![1, 2, 3]);
+        writer.add_tile(TMSTileCoords::from_zxy(1, 1, 1), vec![4, 5, 6]);
+
+        let mut out = Vec::new();
+        writer.write(&mut out, TileType::Png).unwrap();
+
+        assert_eq!(&out[0..7], b"PMTiles");
+        assert_eq!(out[7], 3);
+        let tile_contents_count = u64::from_le_bytes(out[88..96].try_into().unwrap());
+        // Two of the three tiles share identical bytes, so only 2 unique blobs should be stored
+        assert_eq!(tile_contents_count, 2);
+        let addressed_tiles_count = u64::from_le_bytes(out[72..80].try_into().unwrap());
+        assert_eq!(addressed_tiles_count, 3);
+        assert_eq!(out[99], TileType::Png as u8);
+        assert_eq!(out[100], 1); // min_zoom
+        assert_eq!(out[101], 1); // max_zoom
+        assert_eq!(out.len() as u64, {
+            let tile_data_offset = u64::from_le_bytes(out[56..64].try_into().unwrap());
+            let tile_data_len = u64::from_le_bytes(out[64..72].try_into().unwrap());
+            tile_data_offset + tile_data_len
+        });
+    }
+}
@@ -6,22 +6,42 @@ use std::io::{BufReader, Read, Write};
 use std::str;
 
 pub fn write_to_ppm(filename: &str, img: &ImageBuffer) -> Result<(), Error> {
-    if img.data_type != DataType::Uint8 {
-        return Err(Error::OtherError(format!(
-            "Only uint8 images are supported, got dtype={:?}",
-            img.data_type
-        )));
-    }
-    if img.nbands != 3 {
-        return Err(Error::OtherError(format!(
-            "Only RGB images are supported, got nbands={}",
-            img.nbands
-        )));
-    }
+    let magic = match img.nbands {
+        1 => "P5",
+        3 => "P6",
+        n => {
+            return Err(Error::OtherError(format!(
+                "Only grayscale or RGB images are supported, got nbands={}",
+                n
+            )))
+        }
+    };
+    let max_val: u32 = match img.data_type {
+        DataType::Uint8 => 255,
+        DataType::Uint16 => 65535,
+        dt => {
+            return Err(Error::OtherError(format!(
+                "Only uint8 or uint16 images are supported, got dtype={:?}",
+                dt
+            )))
+        }
+    };
 
     let mut file = std::fs::File::create(filename)?;
-    file.write_all(format!("P6 {} {} 255\n", img.width, img.height).as_bytes())?;
-    file.write_all(&img.data)?;
+    file.write_all(format!("{} {} {} {}\n", magic, img.width, img.height, max_val).as_bytes())?;
+    // PNM's multi-byte samples are big-endian ("most significant byte first" per the format spec),
+    // while `ImageBuffer::data` is always stored little-endian - see `DataType::read_sample`'s doc
+    // comment - so 16-bit samples need byte-swapping on the way out.
+    if img.data_type == DataType::Uint16 {
+        let mut swapped = Vec::with_capacity(img.data.len());
+        for pair in img.data.chunks_exact(2) {
+            swapped.push(pair[1]);
+            swapped.push(pair[0]);
+        }
+        file.write_all(&swapped)?;
+    } else {
+        file.write_all(&img.data)?;
+    }
     Ok(())
 }
 
@@ -35,18 +55,22 @@ pub fn read_ppm(filename: &str) -> Result<ImageBuffer, Error> {
     let f = std::fs::File::open(filename)?;
     let mut r = BufReader::new(f);
     // Magic number
-    {
+    let nbands = {
         let mut magic = vec![0u8; 2];
         r.read_exact(&mut magic)?;
-        if magic[0] != b'P' && magic[1] != b'6' {
-            return Err(Error::OtherError(format!(
-                "Unexpected magic number: {:?}",
-                magic
-            )));
+        match (magic[0], magic[1]) {
+            (b'P', b'5') => 1,
+            (b'P', b'6') => 3,
+            _ => {
+                return Err(Error::OtherError(format!(
+                    "Unexpected magic number: {:?}",
+                    magic
+                )))
+            }
         }
-    }
+    };
     // Rest of header line
-    let (width, height) = {
+    let (width, height, data_type) = {
         let mut line = vec![0u8; 0];
         let mut buf = vec![0u8; 1];
         loop {
@@ -76,20 +100,30 @@ pub fn read_ppm(filename: &str) -> Result<ImageBuffer, Error> {
         let width = splits[0].parse::<usize>()?;
         let height = splits[1].parse::<usize>()?;
         let max_val = splits[2].parse::<usize>()?;
-        if max_val != 255 {
-            return Err(Error::OtherError(format!("Invalid max_val={}", max_val)));
-        }
-        (width, height)
+        let data_type = match max_val {
+            255 => DataType::Uint8,
+            65535 => DataType::Uint16,
+            _ => return Err(Error::OtherError(format!("Invalid max_val={}", max_val))),
+        };
+        (width, height, data_type)
     };
-    // Read the data
-    let mut data = vec![0u8; width * height * 3];
+    // Read the data, byte-swapping 16-bit samples back from PNM's big-endian on-disk order to the
+    // little-endian order `ImageBuffer::data` always uses (see `write_to_ppm`).
+    let sample_bytes = data_type.size_bytes();
+    let mut data = vec![0u8; width * height * nbands * sample_bytes];
     r.read_exact(&mut data)?;
+    if data_type == DataType::Uint16 {
+        for pair in data.chunks_exact_mut(2) {
+            pair.swap(0, 1);
+        }
+    }
     Ok(ImageBuffer {
         width,
         height,
-        nbands: 3,
+        nbands,
         has_alpha: false,
-        data_type: DataType::Uint8,
+        data_type,
+        nodata: None,
         data,
     })
 }
@@ -109,6 +143,7 @@ mod tests {
                 nbands: 3,
                 has_alpha: false,
                 data_type: DataType::Uint8,
+                nodata: None,
                 data: data.clone(),
             },
         )
@@ -119,4 +154,29 @@ mod tests {
         assert_eq!(actual_img.nbands, 3);
         assert_eq!(actual_img.data, data);
     }
+
+    #[test]
+    fn test_write_read_ppm_grayscale_uint16() {
+        // 0, 1, 256, 65535, little-endian as `ImageBuffer::data` always stores samples
+        let data = vec![0u8, 0u8, 1u8, 0u8, 0u8, 1u8, 255u8, 255u8];
+        super::write_to_ppm(
+            "/tmp/test_gray16.ppm",
+            &ImageBuffer {
+                width: 4,
+                height: 1,
+                nbands: 1,
+                has_alpha: false,
+                data_type: DataType::Uint16,
+                nodata: None,
+                data: data.clone(),
+            },
+        )
+        .unwrap();
+        let actual_img = super::read_ppm("/tmp/test_gray16.ppm").unwrap();
+        assert_eq!(actual_img.width, 4);
+        assert_eq!(actual_img.height, 1);
+        assert_eq!(actual_img.nbands, 1);
+        assert_eq!(actual_img.data_type, DataType::Uint16);
+        assert_eq!(actual_img.data, data);
+    }
 }
@@ -0,0 +1,128 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+use tokio::sync::Mutex as TokioMutex;
+
+use super::Source;
+use crate::Error;
+
+impl From<Error> for io::Error {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::IO(e) => e,
+            other => io::Error::other(format!("{:?}", other)),
+        }
+    }
+}
+
+enum ReadState {
+    Idle,
+    // Owns a future that clones the `Arc<Mutex<Source>>` rather than borrowing `Source` directly,
+    // so it can be stored here across `poll_read` calls without running into self-referential
+    // lifetime issues.
+    Reading(Pin<Box<dyn Future<Output = Result<Vec<u8>, Error>> + Send>>),
+}
+
+/// Adapts a `Source` into something implementing `tokio::io::AsyncRead` + `AsyncSeek`, so it can
+/// plug into the broader tokio/futures ecosystem (e.g. `tokio_util::io::ReaderStream`, or any
+/// decoder that's generic over `AsyncRead`) instead of only being usable through our own
+/// `read_exact`/`read_at` methods.
+pub struct AsyncSourceReader {
+    source: Arc<TokioMutex<Source>>,
+    pos: u64,
+    state: ReadState,
+}
+
+impl AsyncSourceReader {
+    pub fn new(source: Source) -> AsyncSourceReader {
+        AsyncSourceReader {
+            source: Arc::new(TokioMutex::new(source)),
+            pos: 0,
+            state: ReadState::Idle,
+        }
+    }
+
+    /// Build a reader sharing an already-wrapped `Source`, e.g. one also used elsewhere through
+    /// the tile server's `CogCache`.
+    pub fn from_shared(source: Arc<TokioMutex<Source>>) -> AsyncSourceReader {
+        AsyncSourceReader {
+            source,
+            pos: 0,
+            state: ReadState::Idle,
+        }
+    }
+}
+
+impl AsyncRead for AsyncSourceReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            match &mut this.state {
+                ReadState::Idle => {
+                    let want = buf.remaining();
+                    if want == 0 {
+                        return Poll::Ready(Ok(()));
+                    }
+                    let source = this.source.clone();
+                    let offset = this.pos;
+                    this.state = ReadState::Reading(Box::pin(async move {
+                        let mut tmp = vec![0u8; want];
+                        let mut source = source.lock().await;
+                        let n = source.read_at(offset, &mut tmp).await?;
+                        tmp.truncate(n);
+                        Ok(tmp)
+                    }));
+                }
+                ReadState::Reading(fut) => match fut.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Err(e)) => {
+                        this.state = ReadState::Idle;
+                        return Poll::Ready(Err(e.into()));
+                    }
+                    Poll::Ready(Ok(data)) => {
+                        this.state = ReadState::Idle;
+                        this.pos += data.len() as u64;
+                        buf.put_slice(&data);
+                        return Poll::Ready(Ok(()));
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl AsyncSeek for AsyncSourceReader {
+    fn start_seek(self: Pin<&mut Self>, position: io::SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        match position {
+            io::SeekFrom::Start(offset) => this.pos = offset,
+            io::SeekFrom::Current(delta) => {
+                this.pos = (this.pos as i64 + delta).try_into().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "seek to negative position")
+                })?;
+            }
+            // We don't track the total source length here, so seeking relative to the end isn't
+            // supported. Callers that need this should read the length out-of-band (e.g. from
+            // the TIFF header they just parsed) and use `SeekFrom::Start` instead.
+            io::SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "AsyncSourceReader does not support SeekFrom::End",
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.pos))
+    }
+}
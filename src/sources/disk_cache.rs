@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex as StdMutex};
+
+use tokio::sync::Mutex as TokioMutex;
+
+use super::CHUNK_SIZE;
+use crate::errors::Error;
+
+/// Where to persist chunks fetched from a remote (`S3`/`GCS`) source, and how much disk space to
+/// let them use.
+#[derive(Clone)]
+pub struct DiskCacheConfig {
+    pub dir: PathBuf,
+    pub max_bytes: u64,
+}
+
+/// Disk-backed second tier for `ChunkCache`, sitting between the in-memory chunk cache and the
+/// network for remote sources. Chunks are stored as one file per chunk under `dir`, named after a
+/// hash of `source_key + chunk_index`, so a process restart can reuse chunks a previous run
+/// already paid a range request for instead of re-fetching them.
+///
+/// Concurrent misses for the same chunk are serialized through `write_locks` so a second reader
+/// never observes a half-written file; writes themselves go to a temp file that's renamed into
+/// place, which is atomic on the same filesystem. Eviction runs opportunistically after each write
+/// rather than on a timer, ordering candidates by file access time (least-recently-used first) -
+/// this keeps the implementation simple at the cost of occasionally drifting a little over
+/// `max_bytes` between writes.
+#[derive(Clone)]
+pub struct DiskChunkCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    write_locks: Arc<StdMutex<HashMap<String, Arc<TokioMutex<()>>>>>,
+}
+
+impl DiskChunkCache {
+    pub fn new(config: DiskCacheConfig) -> Self {
+        DiskChunkCache {
+            dir: config.dir,
+            max_bytes: config.max_bytes,
+            write_locks: Arc::new(StdMutex::new(HashMap::new())),
+        }
+    }
+
+    fn cache_key(source_key: &str, chunk_index: u32) -> String {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        source_key.hash(&mut hasher);
+        chunk_index.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    fn chunk_path(&self, source_key: &str, chunk_index: u32) -> PathBuf {
+        self.dir
+            .join(format!("{}.chunk", Self::cache_key(source_key, chunk_index)))
+    }
+
+    /// Returns the chunk if it's present on disk. Reading the file is what keeps its access time
+    /// fresh for LRU eviction (best-effort: filesystems mounted `noatime` won't update it, in
+    /// which case we just evict less precisely).
+    pub async fn read(&self, source_key: &str, chunk_index: u32) -> Option<[u8; CHUNK_SIZE]> {
+        let path = self.chunk_path(source_key, chunk_index);
+        let data = tokio::fs::read(&path).await.ok()?;
+        let data: [u8; CHUNK_SIZE] = data.try_into().ok()?;
+        Some(data)
+    }
+
+    /// Persists `chunk` for `(source_key, chunk_index)`, unless another writer already did so
+    /// while we were waiting for the per-key lock.
+    pub async fn write(
+        &self,
+        source_key: &str,
+        chunk_index: u32,
+        chunk: &[u8; CHUNK_SIZE],
+    ) -> Result<(), Error> {
+        let key = Self::cache_key(source_key, chunk_index);
+        let lock = {
+            let mut locks = self.write_locks.lock().unwrap();
+            locks
+                .entry(key.clone())
+                .or_insert_with(|| Arc::new(TokioMutex::new(())))
+                .clone()
+        };
+        let _guard = lock.lock().await;
+
+        let path = self.dir.join(format!("{}.chunk", key));
+        if tokio::fs::metadata(&path).await.is_ok() {
+            // Someone else wrote this chunk while we were waiting for the lock.
+            return Ok(());
+        }
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let tmp_path = self
+            .dir
+            .join(format!("{}.chunk.tmp.{}", key, std::process::id()));
+        tokio::fs::write(&tmp_path, chunk).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+
+        self.evict_until_within_budget().await;
+        Ok(())
+    }
+
+    async fn evict_until_within_budget(&self) {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        let mut files = Vec::new();
+        let mut total_bytes: u64 = 0;
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            let accessed = metadata
+                .accessed()
+                .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+            total_bytes += metadata.len();
+            files.push((entry.path(), accessed, metadata.len()));
+        }
+        if total_bytes <= self.max_bytes {
+            return;
+        }
+        files.sort_by_key(|(_, accessed, _)| *accessed);
+        for (path, _, len) in files {
+            if total_bytes <= self.max_bytes {
+                break;
+            }
+            if tokio::fs::remove_file(&path).await.is_ok() {
+                total_bytes = total_bytes.saturating_sub(len);
+            }
+        }
+    }
+}
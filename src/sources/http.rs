@@ -0,0 +1,71 @@
+use std::cmp::min;
+
+use bytes::Buf;
+use reqwest::{Client, Response};
+
+use crate::errors::Error;
+
+#[derive(Debug, Default)]
+struct Stats {
+    requests_count: usize,
+}
+
+/// Reads from an arbitrary `http(s)://` URL via unauthenticated range GETs. Unlike `S3Source`/
+/// `GCSSource`, there's no credential provider or request signing involved - this is meant for
+/// publicly readable COGs hosted on a generic object store or CDN.
+pub struct HttpSource {
+    client: Client,
+    url: String,
+    stats: Stats,
+}
+
+impl HttpSource {
+    pub fn new(url: &str) -> Result<HttpSource, Error> {
+        let client = Client::builder().build()?;
+        Ok(HttpSource {
+            client,
+            url: url.to_string(),
+            stats: Default::default(),
+        })
+    }
+
+    async fn do_request(&mut self, from: u64, to: u64) -> Result<Response, Error> {
+        self.stats.requests_count += 1;
+        Ok(self
+            .client
+            .get(&self.url)
+            .header("Range", format!("bytes={}-{}", from, to))
+            .send()
+            .await?)
+    }
+
+    pub async fn read(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut body = {
+            let resp = self
+                .do_request(offset, offset + buf.len() as u64)
+                .await?;
+            // Same 206-vs-error handling as `S3Source`/`GCSSource`: a server that doesn't support
+            // range requests could reply 200 with the whole document, but we don't want that here,
+            // and EOF is implicitly handled by the server returning fewer bytes than requested
+            // (see the `Content-Range` discussion in those sources).
+            if resp.status().as_u16() == 206 {
+                resp.bytes().await?
+            } else {
+                return Err(Error::OtherError(format!(
+                    "Request failed, code={}: {}",
+                    resp.status().as_u16(),
+                    resp.text().await?,
+                )));
+            }
+        };
+
+        let body_len = body.remaining();
+        let len_to_copy = min(body_len, buf.len());
+        body.copy_to_slice(&mut buf[0..len_to_copy]);
+        Ok(len_to_copy)
+    }
+
+    pub fn get_stats(&self) -> String {
+        format!("{:?}", self.stats)
+    }
+}
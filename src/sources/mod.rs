@@ -1,16 +1,25 @@
 use std::{fmt, io::ErrorKind};
 
+mod async_reader;
 mod auth;
+mod cached_source;
+mod disk_cache;
 mod file;
 #[cfg(feature = "gcs")]
 mod gcs;
+mod http;
 mod memory;
 mod s3;
 
+pub use async_reader::AsyncSourceReader;
+pub use cached_source::{CachedSource, SourceBackend};
+pub use disk_cache::DiskCacheConfig;
 pub use file::FileSource;
 pub use gcs::GCSSource;
+pub use http::HttpSource;
 pub use memory::MemorySource;
 pub use s3::S3Source;
+use disk_cache::DiskChunkCache;
 use std::collections::HashMap;
 
 use crate::errors::Error;
@@ -20,6 +29,7 @@ enum SourceKind {
     File(FileSource),
     S3(S3Source),
     Gcs(GCSSource),
+    Http(HttpSource),
     #[allow(dead_code)] // This is used for testing
     Memory(MemorySource),
 }
@@ -30,6 +40,7 @@ impl fmt::Debug for SourceKind {
             Self::File(_) => f.debug_tuple("File").finish(),
             Self::S3(_) => f.debug_tuple("S3").finish(),
             Self::Gcs(_) => f.debug_tuple("GCS").finish(),
+            Self::Http(_) => f.debug_tuple("Http").finish(),
             Self::Memory(_) => f.debug_tuple("Memory").finish(),
         }
     }
@@ -43,6 +54,7 @@ impl SourceKind {
             SourceKind::File(s) => s.read(offset, buf).await,
             SourceKind::S3(s) => s.read(offset, buf).await,
             SourceKind::Gcs(s) => s.read(offset, buf).await,
+            SourceKind::Http(s) => s.read(offset, buf).await,
             SourceKind::Memory(s) => s.read(offset, buf).await,
         }
     }
@@ -64,6 +76,7 @@ impl SourceKind {
             SourceKind::File(s) => s.get_stats(),
             SourceKind::S3(s) => s.get_stats(),
             SourceKind::Gcs(s) => s.get_stats(),
+            SourceKind::Http(s) => s.get_stats(),
             SourceKind::Memory(s) => s.get_stats(),
         }
     }
@@ -71,7 +84,10 @@ impl SourceKind {
 
 const CHUNK_SIZE: usize = 16384; // 16 kB, like GDAL `CPL_VSIL_CURL_CHUNK_SIZE`
 
-const MAX_CACHED_CHUNKS: usize = 100;
+// Byte-budgeted rather than count-based so that the memory cost of the cache doesn't silently
+// change if CHUNK_SIZE is ever tuned. 16 MiB is ~1000 chunks at the current CHUNK_SIZE, which is
+// comfortably more than the handful of chunks a single IFD/header read needs.
+const MAX_CACHE_BYTES: usize = 16 * 1024 * 1024;
 
 /// Sources support chunked reading mode with caching and direct reading.
 /// - Chunked reading with caching should be uses to tread the header + IFDs
@@ -94,60 +110,131 @@ struct ChunkCache {
     // Maps a chunk index to the chunk data. Note that the last chunk will still have CHUNK_SIZE
     // data, but data past `source_len` will be filled with 0
     chunks_cache: HashMap<u32, [u8; CHUNK_SIZE]>,
+    // Chunk indices in least-recently-used order: `lru_order.front()` is the next one to evict,
+    // `lru_order.back()` was the most recently touched. Kept in sync with `chunks_cache`.
+    lru_order: std::collections::VecDeque<u32>,
     // Once we have reached EOF, we store the source len here
     source_len: Option<u64>,
+    // Maximum total size of `chunks_cache`, in bytes. Configurable (rather than a bare constant)
+    // so callers reading many small files at once can trade memory for hit rate.
+    budget_bytes: usize,
+    hits: usize,
+    misses: usize,
+    // Key identifying the source this cache belongs to (currently just the source spec it was
+    // opened from), used to namespace entries in `disk_cache`.
+    source_key: String,
+    // Optional second tier consulted on an in-memory miss, letting a remote source's chunks
+    // survive a process restart. See `DiskChunkCache` for the on-disk layout.
+    disk_cache: Option<DiskChunkCache>,
+    disk_hits: usize,
 }
 
 impl fmt::Debug for ChunkCache {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("BufferedSourceReader")
             .field("source_len", &self.source_len)
+            .field("hits", &self.hits)
+            .field("misses", &self.misses)
             .finish()
     }
 }
 
 impl ChunkCache {
-    pub fn new() -> Self {
+    pub fn new(
+        budget_bytes: usize,
+        source_key: String,
+        disk_cache: Option<DiskChunkCache>,
+    ) -> Self {
         ChunkCache {
             chunks_cache: HashMap::new(),
+            lru_order: std::collections::VecDeque::new(),
             source_len: None,
+            budget_bytes,
+            hits: 0,
+            misses: 0,
+            source_key,
+            disk_cache,
+            disk_hits: 0,
         }
     }
 
+    // Marks `chunk_index` as the most recently used entry
+    fn touch(&mut self, chunk_index: u32) {
+        if let Some(pos) = self.lru_order.iter().position(|c| *c == chunk_index) {
+            self.lru_order.remove(pos);
+        }
+        self.lru_order.push_back(chunk_index);
+    }
+
+    fn evict_until_within_budget(&mut self) {
+        while self.chunks_cache.len() * CHUNK_SIZE > self.budget_bytes {
+            match self.lru_order.pop_front() {
+                Some(key) => {
+                    self.chunks_cache.remove(&key);
+                }
+                // Shouldn't happen (lru_order and chunks_cache are kept in sync), but avoid
+                // looping forever if it ever does
+                None => break,
+            }
+        }
+    }
+
+    pub fn get_stats(&self) -> String {
+        format!(
+            "cache_hits={}, cache_misses={}, disk_cache_hits={}",
+            self.hits, self.misses, self.disk_hits
+        )
+    }
+
     async fn read_chunk(
         &mut self,
         source_kind: &mut SourceKind,
         chunk_index: u32,
     ) -> Result<&[u8; CHUNK_SIZE], Error> {
-        if self.chunks_cache.len() >= MAX_CACHED_CHUNKS {
-            // Here, a LRU cache would probably be a better idea. For now we just evict randomly
-            // a page for simplicity's sake
-            let key = *self.chunks_cache.keys().next().unwrap();
-            self.chunks_cache.remove(&key);
+        if self.chunks_cache.contains_key(&chunk_index) {
+            self.hits += 1;
+            self.touch(chunk_index);
+            return Ok(self.chunks_cache.get(&chunk_index).unwrap());
+        }
+        self.misses += 1;
+
+        if let Some(disk_cache) = &self.disk_cache {
+            if let Some(chunk) = disk_cache.read(&self.source_key, chunk_index).await {
+                self.disk_hits += 1;
+                self.chunks_cache.insert(chunk_index, chunk);
+                self.touch(chunk_index);
+                self.evict_until_within_budget();
+                return Ok(self.chunks_cache.get(&chunk_index).unwrap());
+            }
         }
 
-        match self.chunks_cache.entry(chunk_index) {
-            std::collections::hash_map::Entry::Occupied(e) => Ok(e.into_mut()),
-            std::collections::hash_map::Entry::Vacant(e) => {
-                let mut chunk = [0u8; CHUNK_SIZE];
-                let read_count = source_kind
-                    .read(chunk_index as u64 * CHUNK_SIZE as u64, &mut chunk)
-                    .await?;
-                if read_count < chunk.len() {
-                    // If we read less than page size, it means we reached EOF. Note that tokio read_buf doc
-                    // say that it's possible that you get an EOF once and then could get more data from the file.
-                    // But I guess this only happen if the file is being written to while you read - which is not
-                    // something we want to handle. So we decide that the first EOF we get is the true EOF
-                    if let Some(source_len) = self.source_len {
-                        return Err(Error::SourceError(format!("Reached EOF a second time (previous source_len={}), now read_count={} at chunk_index={}", source_len, read_count, chunk_index)));
-                    } else {
-                        self.source_len =
-                            Some(chunk_index as u64 * CHUNK_SIZE as u64 + read_count as u64);
-                    }
-                }
-                return Ok(e.insert(chunk));
+        let mut chunk = [0u8; CHUNK_SIZE];
+        let read_count = source_kind
+            .read(chunk_index as u64 * CHUNK_SIZE as u64, &mut chunk)
+            .await?;
+        if read_count < chunk.len() {
+            // If we read less than page size, it means we reached EOF. Note that tokio read_buf doc
+            // say that it's possible that you get an EOF once and then could get more data from the file.
+            // But I guess this only happen if the file is being written to while you read - which is not
+            // something we want to handle. So we decide that the first EOF we get is the true EOF
+            if let Some(source_len) = self.source_len {
+                return Err(Error::SourceError(format!("Reached EOF a second time (previous source_len={}), now read_count={} at chunk_index={}", source_len, read_count, chunk_index)));
+            } else {
+                self.source_len =
+                    Some(chunk_index as u64 * CHUNK_SIZE as u64 + read_count as u64);
             }
         }
+
+        if let Some(disk_cache) = &self.disk_cache {
+            disk_cache.write(&self.source_key, chunk_index, &chunk).await?;
+        }
+
+        self.chunks_cache.insert(chunk_index, chunk);
+        self.touch(chunk_index);
+        // Evict only after inserting the new chunk, so a cache sized for N chunks can still
+        // always hold the one that was just read even if it's briefly over budget
+        self.evict_until_within_budget();
+        Ok(self.chunks_cache.get(&chunk_index).unwrap())
     }
 
     pub async fn read_exact(
@@ -194,29 +281,47 @@ pub struct Source {
 }
 
 impl Source {
-    fn new(kind: SourceKind) -> Source {
+    fn new(kind: SourceKind, source_key: String) -> Source {
         Source {
             kind,
-            cache: ChunkCache::new(),
+            cache: ChunkCache::new(MAX_CACHE_BYTES, source_key, None),
         }
     }
 
     pub async fn new_from_source_spec(source_spec: &str) -> Result<Source, Error> {
+        Source::new_from_source_spec_with_config(source_spec, MAX_CACHE_BYTES, None).await
+    }
+
+    /// Same as `new_from_source_spec`, but lets the in-memory chunk cache's byte budget and an
+    /// optional on-disk second tier be overridden instead of defaulting to `MAX_CACHE_BYTES` and
+    /// no disk cache. `disk_cache` only takes effect for remote (`S3`/`GCS`) sources - a `File`
+    /// source is already backed by disk, so persisting its chunks again would be pointless.
+    pub async fn new_from_source_spec_with_config(
+        source_spec: &str,
+        cache_budget_bytes: usize,
+        disk_cache: Option<DiskCacheConfig>,
+    ) -> Result<Source, Error> {
         let source_string = source_spec.to_string();
-        if source_string.starts_with("/vsis3/") {
-            let source = Source::new(SourceKind::S3(
-                S3Source::new(source_string.strip_prefix("/vsis3/").unwrap()).await?,
-            ));
-            Ok(source)
+        let kind = if source_string.starts_with("/vsis3/") {
+            SourceKind::S3(S3Source::new(source_string.strip_prefix("/vsis3/").unwrap()).await?)
         } else if source_string.starts_with("/vsigs/") {
-            let source = Source::new(SourceKind::Gcs(
-                GCSSource::new(source_string.strip_prefix("/vsigs/").unwrap()).await?,
-            ));
-            Ok(source)
+            SourceKind::Gcs(GCSSource::new(source_string.strip_prefix("/vsigs/").unwrap()).await?)
+        } else if let Some(url) = source_string.strip_prefix("/vsicurl/") {
+            SourceKind::Http(HttpSource::new(url)?)
+        } else if source_string.starts_with("http://") || source_string.starts_with("https://") {
+            SourceKind::Http(HttpSource::new(&source_string)?)
         } else {
-            let source = Source::new(SourceKind::File(FileSource::new(&source_string).await?));
-            Ok(source)
-        }
+            SourceKind::File(FileSource::new(&source_string).await?)
+        };
+        let disk_cache = match (&kind, disk_cache) {
+            (SourceKind::File(_), _) => None,
+            (_, Some(config)) => Some(DiskChunkCache::new(config)),
+            (_, None) => None,
+        };
+        Ok(Source {
+            kind,
+            cache: ChunkCache::new(cache_budget_bytes, source_string, disk_cache),
+        })
     }
 
     // Read going through the chunk cache
@@ -229,8 +334,15 @@ impl Source {
         self.kind.read_exact(offset, buf).await
     }
 
+    // Read bypassing the chunk cache, returning however many bytes were actually read instead of
+    // erroring on a short read. This is what `AsyncSourceReader` uses to adapt `Source` to
+    // tokio's `AsyncRead`, where a short read at EOF is expected rather than exceptional.
+    pub async fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize, Error> {
+        self.kind.read(offset, buf).await
+    }
+
     pub fn get_stats(&self) -> String {
-        self.kind.get_stats()
+        format!("{}, {}", self.kind.get_stats(), self.cache.get_stats())
     }
 }
 
@@ -253,7 +365,8 @@ mod tests {
             let mut data = vec![0u8; data_len];
             random_buf(&mut data);
 
-            let mut mem_source = Source::new(SourceKind::Memory(MemorySource::new(data.clone())));
+            let mut mem_source =
+                Source::new(SourceKind::Memory(MemorySource::new(data.clone())), "test".to_string());
 
             for offset in [0, 50, 1026] {
                 if offset > data_len {
@@ -274,7 +387,8 @@ mod tests {
         let mut data = vec![0u8; 2000];
         random_buf(&mut data);
 
-        let mut mem_source = Source::new(SourceKind::Memory(MemorySource::new(data.clone())));
+        let mut mem_source =
+            Source::new(SourceKind::Memory(MemorySource::new(data.clone())), "test".to_string());
 
         let offset = 513;
         let mut out = vec![0u8; data.len() - offset];
@@ -298,7 +412,8 @@ mod tests {
         let mut data = vec![0u8; 2000];
         random_buf(&mut data);
 
-        let mut mem_source = Source::new(SourceKind::Memory(MemorySource::new(data.clone())));
+        let mut mem_source =
+            Source::new(SourceKind::Memory(MemorySource::new(data.clone())), "test".to_string());
 
         let offset = 513;
         let mut out = vec![0u8; data.len() - offset];
@@ -322,7 +437,8 @@ mod tests {
         let mut data = vec![0u8; 50];
         random_buf(&mut data);
 
-        let mut mem_source = Source::new(SourceKind::Memory(MemorySource::new(data.clone())));
+        let mut mem_source =
+            Source::new(SourceKind::Memory(MemorySource::new(data.clone())), "test".to_string());
 
         let mut out = vec![0u8; 10];
         let res = mem_source.read_exact(45, &mut out).await;
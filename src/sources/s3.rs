@@ -1,8 +1,13 @@
 use std::cmp::min;
 
-use super::auth::aws::sign_request;
+use super::auth::aws::{
+    clock_offset_from_server_time, parse_http_date, sign_request_with_clock_offset,
+    CredentialProvider,
+};
 use crate::errors::Error;
+use base64::Engine;
 use bytes::Buf;
+use chrono::Duration;
 use reqwest::{Client, Response};
 
 #[derive(Debug, Default)]
@@ -12,16 +17,46 @@ struct Stats {
 
 pub struct S3Source {
     client: Client,
+    credentials: CredentialProvider,
     blob_name: String,
+    // Correction applied to the local clock when signing, learned from the `Date` header of a
+    // prior response. Keeps requests from failing with `RequestTimeTooSkewed` on a machine whose
+    // clock has drifted.
+    clock_offset: Option<Duration>,
+    // SSE-C customer-provided key (raw 256-bit key bytes), read from `AWS_SSE_C_KEY` (base64) if
+    // set. When present, every ranged GET must present it or the bucket rejects the request with
+    // a 400, since S3 never stores the plaintext key and can't decrypt without it.
+    sse_customer_key: Option<[u8; 32]>,
     stats: Stats,
 }
 
+// Reads the SSE-C customer key from `AWS_SSE_C_KEY` (base64-encoded, like `--sse-customer-key`
+// input to the AWS CLI), if set.
+fn sse_customer_key_from_env() -> Result<Option<[u8; 32]>, Error> {
+    let Ok(encoded) = std::env::var("AWS_SSE_C_KEY") else {
+        return Ok(None);
+    };
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| Error::OtherError(format!("AWS_SSE_C_KEY is not valid base64: {}", e)))?;
+    let key: [u8; 32] = decoded.try_into().map_err(|v: Vec<u8>| {
+        Error::OtherError(format!(
+            "AWS_SSE_C_KEY must decode to a 256-bit (32-byte) key, got {} bytes",
+            v.len()
+        ))
+    })?;
+    Ok(Some(key))
+}
+
 impl S3Source {
     pub async fn new(filename: &str) -> Result<S3Source, Error> {
         let client = Client::builder().build()?;
         Ok(S3Source {
             client,
+            credentials: CredentialProvider::new(),
             blob_name: filename.to_string(),
+            clock_offset: None,
+            sse_customer_key: sse_customer_key_from_env()?,
             stats: Default::default(),
         })
     }
@@ -33,17 +68,73 @@ impl S3Source {
         from: u64,
         to: u64,
     ) -> Result<Response, Error> {
-        let headers = sign_request("GET", host, uri)?;
+        let range = format!("bytes={}-{}", from, to);
+        let creds = self.credentials.get_credentials(&self.client).await?;
+        let mut extra_headers = vec![("range".to_string(), range.clone())];
+        let sse_headers = self.sse_customer_key.map(|key| {
+            let key_b64 = base64::engine::general_purpose::STANDARD.encode(key);
+            let key_md5_b64 = base64::engine::general_purpose::STANDARD.encode(md5::compute(key).0);
+            (key_b64, key_md5_b64)
+        });
+        if let Some((key_b64, key_md5_b64)) = &sse_headers {
+            extra_headers.push((
+                "x-amz-server-side-encryption-customer-algorithm".to_string(),
+                "AES256".to_string(),
+            ));
+            extra_headers.push((
+                "x-amz-server-side-encryption-customer-key".to_string(),
+                key_b64.clone(),
+            ));
+            extra_headers.push((
+                "x-amz-server-side-encryption-customer-key-MD5".to_string(),
+                key_md5_b64.clone(),
+            ));
+        }
+        let headers = sign_request_with_clock_offset(
+            creds,
+            "GET",
+            host,
+            uri,
+            &extra_headers,
+            self.clock_offset,
+        )?;
         let url = format!("http://{host}{uri}");
         self.stats.requests_count += 1;
-        let req = self
+        let mut req = self
             .client
             .get(url)
             .header("Authorization", headers.authorization_header)
-            .header("Range", format!("bytes={}-{}", from, to))
+            .header("Range", range)
             .header("Host", headers.host_header)
             .header("x-amz-date", headers.amz_date_header);
-        Ok(req.send().await?)
+        if let Some(security_token) = headers.security_token_header {
+            req = req.header("x-amz-security-token", security_token);
+        }
+        if let Some((key_b64, key_md5_b64)) = &sse_headers {
+            req = req
+                .header(
+                    "x-amz-server-side-encryption-customer-algorithm",
+                    "AES256",
+                )
+                .header("x-amz-server-side-encryption-customer-key", key_b64)
+                .header(
+                    "x-amz-server-side-encryption-customer-key-MD5",
+                    key_md5_b64,
+                );
+        }
+        let resp = req.send().await?;
+        // Learn the server's clock from its response so that a follow-up request (or a retry, if
+        // this one got rejected for `RequestTimeTooSkewed`) signs with a corrected timestamp.
+        // TODO: Retry this request immediately on a `RequestTimeTooSkewed` (403) response instead
+        // of only correcting future requests.
+        if let Some(date_header) = resp.headers().get("date") {
+            if let Ok(date_str) = date_header.to_str() {
+                if let Ok(server_time) = parse_http_date(date_str) {
+                    self.clock_offset = Some(clock_offset_from_server_time(server_time));
+                }
+            }
+        }
+        Ok(resp)
     }
 
     pub async fn read(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize, Error> {
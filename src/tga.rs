@@ -124,6 +124,7 @@ pub fn read_tga(filename: &str) -> Result<ImageBuffer, Error> {
         has_alpha: true,
         nbands: 4,
         data_type: DataType::Uint8,
+        nodata: None,
         data,
     })
 }
@@ -145,6 +146,7 @@ mod tests {
                 nbands: 3,
                 has_alpha: false,
                 data_type: DataType::Uint8,
+                nodata: None,
                 data: data.clone(),
             },
         )
@@ -168,6 +170,7 @@ mod tests {
                 nbands: 4,
                 has_alpha: true,
                 data_type: DataType::Uint8,
+                nodata: None,
                 data: data.clone(),
             },
         )
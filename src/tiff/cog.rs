@@ -4,10 +4,12 @@ use super::data_types::InternalDataType;
 use super::geo_keys::GeoKeyDirectory;
 use super::georef::{Georeference, Geotransform};
 use super::ifd::{IFDTag, IFDValue, ImageFileDirectory, TIFFReader};
-use super::tags::PhotometricInterpretation;
+use super::predictor::Predictor;
+use super::tags::{ColorMap, PhotometricInterpretation};
 use crate::bbox::BoundingBox;
 use crate::image;
-use crate::image::{DataType, ImageBuffer};
+use crate::image::{DataType, ImageBuffer, ResampleAlg};
+use crate::math::Vec2f;
 use crate::sources::Source;
 use crate::Error;
 use proj::Transform;
@@ -44,7 +46,7 @@ impl BandsInterpretation {
         photometric_interpretation: PhotometricInterpretation,
     ) -> Result<BandsInterpretation, Error> {
         match photometric_interpretation {
-            PhotometricInterpretation::BlackIsZero => {
+            PhotometricInterpretation::BlackIsZero | PhotometricInterpretation::WhiteIsZero => {
                 // Multispectral images. Typically extra_samples will be all 0
                 if !extra_samples.iter().all(|x| *x == 0) {
                     Err(Error::OtherError(format!(
@@ -97,6 +99,46 @@ impl BandsInterpretation {
                     ))),
                 }
             }
+            PhotometricInterpretation::Separated => {
+                // CMYK. Same ExtraSamples=2 => unassociated alpha convention as Rgb/YCbCr, just
+                // shifted up by the extra ink channel.
+                match extra_samples {
+                    [] => {
+                        if nbands != 4 {
+                            Err(Error::OtherError(format!(
+                                "Got nbands != 4 ({:?}) for CMYK color interpretation without extra samples",
+                                nbands
+                            )))
+                        } else {
+                            Ok(BandsInterpretation {
+                                nbands: 4,
+                                has_alpha: false,
+                            })
+                        }
+                    }
+                    [2] => {
+                        if nbands != 5 {
+                            Err(Error::OtherError(format!(
+                                "Got nbands != 5 ({:?}) for CMYK color interpretation with extra samples",
+                                nbands
+                            )))
+                        } else {
+                            Ok(BandsInterpretation {
+                                nbands: 5,
+                                has_alpha: true,
+                            })
+                        }
+                    }
+                    _ => Err(Error::OtherError(format!(
+                        "Unable to interpret extra_samples for CMYK image: {:?}",
+                        extra_samples
+                    ))),
+                }
+            }
+            PhotometricInterpretation::CIELab => Err(Error::UnsupportedTagValue(
+                IFDTag::PhotometricInterpretation,
+                "CIELab has no RGB conversion implemented".to_string(),
+            )),
             PhotometricInterpretation::Mask => {
                 if nbands != 1 {
                     Err(Error::OtherError(format!(
@@ -115,6 +157,25 @@ impl BandsInterpretation {
                     })
                 }
             }
+            PhotometricInterpretation::Palette => {
+                // A single band of indices into the overview's ColorMap
+                if nbands != 1 {
+                    Err(Error::OtherError(format!(
+                        "Got nbands != 1 ({:?}) for palette color interpretation",
+                        nbands
+                    )))
+                } else if !extra_samples.is_empty() {
+                    Err(Error::OtherError(format!(
+                        "Got extra_samples for palette band: {:?}",
+                        extra_samples
+                    )))
+                } else {
+                    Ok(BandsInterpretation {
+                        nbands: 1,
+                        has_alpha: false,
+                    })
+                }
+            }
         }
     }
 }
@@ -127,12 +188,58 @@ pub struct Overview {
     pub tile_height: u64,
     pub bands: BandsInterpretation,
     pub photometric_interpretation: PhotometricInterpretation,
+    // Set when `photometric_interpretation` is `Palette`, in which case this overview's single
+    // band holds indices into it rather than a visualizable value.
+    pub color_map: Option<ColorMap>,
     pub ifd: ImageFileDirectory,
     pub is_full_resolution: bool,
     pub compression: Compression,
+    pub predictor: Predictor,
+    // Whether this overview is laid out as TIFF strips (`RowsPerStrip`/`StripOffsets`/
+    // `StripByteCounts`) rather than tiles. When true, `tile_width` is the full image width and
+    // `tile_height` is `RowsPerStrip`, and the last block's byte count covers only the strip's
+    // actual remaining rows rather than being padded out to `tile_height` (see
+    // `OverviewDataReader::read_image_part_into`).
+    is_striped: bool,
+    // Whether this overview uses PlanarConfiguration=2: each band is stored as its own plane of
+    // tiles/strips instead of one interleaved plane (PlanarConfiguration=1).
+    is_planar: bool,
     data_type: InternalDataType,
+    // Set when `photometric_interpretation` is `YCbCr` and the overview isn't JPEG-compressed (a
+    // JPEG decoder already emits RGB on its own), in which case this is the `ReferenceBlackWhite`
+    // tag value (or its spec default) for `ImageBuffer::ycbcr_to_rgb` to scale samples by.
+    pub reference_black_white: Option<[f64; 6]>,
 }
 
+/// Structured result of `COG::validate`: `errors` are spec violations that would make the file
+/// unreadable or unreliable (on top of whatever `COG::open` already refused to open), `warnings`
+/// flag things that merely hurt tiling performance (e.g. an unusual block size). `block_sizes` and
+/// `overview_decimations` report what was detected, in overview order, for diagnostics even when
+/// the file is fine.
+#[derive(Debug, Clone, Default)]
+pub struct COGValidationReport {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    // (tile_width, tile_height) for each overview, in `COG::overviews` order
+    pub block_sizes: Vec<(u64, u64)>,
+    // `overviews[i - 1].width / overviews[i].width` for each i >= 1 - ideally close to a power of
+    // two (most commonly 2)
+    pub overview_decimations: Vec<f64>,
+}
+
+impl COGValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+// When fetching the tiles needed for a `read_image_part`, any two tiles whose byte ranges are
+// within this many bytes of each other get coalesced into a single `read_exact_direct` call
+// instead of one GET per tile. This trades a bit of wasted bandwidth (the gap bytes are read but
+// discarded) for far fewer round-trips against remote/object-store sources, where request count
+// tends to dominate latency.
+pub const DEFAULT_TILE_MERGE_GAP_BYTES: u64 = 16 * 1024;
+
 #[derive(Debug)]
 pub struct OverviewDataReader {
     pub width: u64,
@@ -143,7 +250,30 @@ pub struct OverviewDataReader {
     tile_offsets: Vec<u64>,
     tile_bytes_counts: Vec<u64>,
     compression: Compression,
+    predictor: Predictor,
+    is_striped: bool,
+    is_planar: bool,
     data_type: InternalDataType,
+    merge_gap: u64,
+}
+
+/// Scratch buffers reused across tiles (and, if the caller keeps it around, across calls) by
+/// `OverviewDataReader::read_image_part_into`, so a tile server issuing thousands of overlapping
+/// reads can amortize its allocations down to near zero instead of allocating two fresh `Vec<u8>`
+/// per tile.
+#[derive(Debug, Default)]
+pub struct TileScratch {
+    // Raw bytes read from `source` for the merged range currently being processed.
+    compressed: Vec<u8>,
+    // Output of `Compression::decompress_into`/`Predictor::reconstruct` for the tile currently
+    // being processed.
+    decompressed: Vec<u8>,
+}
+
+impl TileScratch {
+    pub fn new() -> TileScratch {
+        TileScratch::default()
+    }
 }
 
 impl Overview {
@@ -152,21 +282,26 @@ impl Overview {
         source: &mut Source,
         photometric_interpretation: PhotometricInterpretation,
     ) -> Result<Overview, Error> {
-        // Check planar configuration is contiguous pixels
-        match ifd
+        // PlanarConfiguration is either 1 (contiguous/interleaved samples - what GDAL always
+        // writes) or 2 (each band stored as its own plane of tiles/strips, one after another in
+        // TileOffsets/StripOffsets). Both are handled here; see `is_planar`'s use in
+        // `OverviewDataReader::read_image_part_into`.
+        let is_planar = match ifd
             .get_tag_value(source, IFDTag::PlanarConfiguration)
             .await?
         {
-            IFDValue::Short(v) => {
-                if v[0] != 1 {
+            IFDValue::Short(v) => match v[..] {
+                [1] => false,
+                [2] => true,
+                _ => {
                     return Err(Error::UnsupportedTagValue(
                         IFDTag::PlanarConfiguration,
                         format!("{:?}", v),
-                    ));
+                    ))
                 }
-            }
+            },
             value => return Err(Error::TagHasWrongType(IFDTag::PlanarConfiguration, value)),
-        }
+        };
         // We only support Orientation = 1 which means the image has origin at top-left
         // (usual image processing axes)
         // Since its defaults to 1 if undefined, it needs to either be defined as 1 or not defined
@@ -186,6 +321,12 @@ impl Overview {
             Err(e) => return Err(e),
         }
         let compression = Compression::from_ifd(source, &ifd).await?;
+        let predictor = match ifd.get_u64_tag_value(source, IFDTag::Predictor).await {
+            Ok(v) => Predictor::from_tag_value(v)?,
+            // The Predictor tag is optional and defaults to 1 (no predictor) when absent
+            Err(Error::RequiredTagNotFound(_)) => Predictor::None,
+            Err(e) => return Err(e),
+        };
         // https://docs.ogc.org/is/21-026/21-026.html
         // 7.2.1. Requirement Reduced-Resolution Subfiles
         let is_full_resolution = match ifd.get_tag_value(source, IFDTag::NewSubfileType).await {
@@ -211,37 +352,138 @@ impl Overview {
             Err(Error::RequiredTagNotFound(IFDTag::ExtraSamples)) => Ok(vec![]),
             Err(e) => Err(e),
         }?;
-        println!(
-            "nbands={:?}, extra_samples={:?}, compression={:?}",
-            nbands, extra_samples, compression
-        );
         let bands = BandsInterpretation::new(nbands, &extra_samples, photometric_interpretation)?;
         let data_type = data_type_from_ifd(&ifd, source).await?;
+        // `Predictor::reconstruct` only knows how to undo the Horizontal/FloatingPoint delta
+        // filter for 8/16/32-bit samples - reject the combination here, at open time, rather than
+        // letting a crafted/malformed COG open successfully and fail the first time a tile using
+        // it is actually decoded.
+        if matches!(predictor, Predictor::Horizontal | Predictor::FloatingPoint)
+            && !matches!(data_type.bit_depth(), 8 | 16 | 32)
+        {
+            return Err(Error::UnsupportedTagValue(
+                IFDTag::Predictor,
+                format!(
+                    "{:?} predictor isn't supported with {}-bit samples",
+                    predictor,
+                    data_type.bit_depth()
+                ),
+            ));
+        }
+        let color_map = match photometric_interpretation {
+            PhotometricInterpretation::Palette => {
+                Some(ColorMap::read_from_ifd(source, &ifd).await?)
+            }
+            _ => None,
+        };
+
+        // YCbCr samples aren't directly displayable - `ImageBuffer::ycbcr_to_rgb` converts them -
+        // except when JPEG-compressed, where the JPEG decoder already hands back RGB bytes.
+        let reference_black_white = match photometric_interpretation {
+            PhotometricInterpretation::YCbCr if !matches!(compression, Compression::Jpeg(_)) => {
+                // YCbCrSubSampling (530) defaults to [2, 2] per spec when absent. The reader below
+                // always treats every band as a full-resolution, pixel-interleaved plane (same as
+                // Rgb), so only the unsubsampled case can be decoded correctly - reject anything
+                // else rather than silently producing a scrambled image.
+                let subsampling = match ifd
+                    .get_vec_u64_tag_value(source, IFDTag::YCbCrSubSampling)
+                    .await
+                {
+                    Ok(v) => v,
+                    Err(Error::RequiredTagNotFound(_)) => vec![2, 2],
+                    Err(e) => return Err(e),
+                };
+                if subsampling != [1, 1] {
+                    return Err(Error::UnsupportedTagValue(
+                        IFDTag::YCbCrSubSampling,
+                        format!(
+                            "{:?} (only unsubsampled 1x1 YCbCr is supported outside of JPEG)",
+                            subsampling
+                        ),
+                    ));
+                }
+                // ReferenceBlackWhite is stored as RATIONAL (the classic TIFF type for this tag),
+                // though some encoders write DOUBLE instead - both are accepted here.
+                let black_white_into_array = |v: Vec<f64>| -> Result<[f64; 6], Error> {
+                    v.try_into().map_err(|v: Vec<f64>| {
+                        Error::OtherError(format!(
+                            "Expected 6 ReferenceBlackWhite values, got {}",
+                            v.len()
+                        ))
+                    })
+                };
+                let black_white = match ifd.get_tag_value(source, IFDTag::ReferenceBlackWhite).await
+                {
+                    Ok(IFDValue::Rational(values)) => black_white_into_array(
+                        values
+                            .iter()
+                            .map(|(num, den)| *num as f64 / *den as f64)
+                            .collect(),
+                    )?,
+                    Ok(IFDValue::Double(values)) => black_white_into_array(values)?,
+                    Ok(other) => {
+                        return Err(Error::TagHasWrongType(IFDTag::ReferenceBlackWhite, other))
+                    }
+                    Err(Error::RequiredTagNotFound(_)) => [0.0, 255.0, 128.0, 255.0, 128.0, 255.0],
+                    Err(e) => return Err(e),
+                };
+                Some(black_white)
+            }
+            _ => None,
+        };
+
+        let width = ifd.get_u64_tag_value(source, IFDTag::ImageWidth).await?;
+        let height = ifd.get_u64_tag_value(source, IFDTag::ImageLength).await?;
+        // TIFF images are either tiled (TileWidth/TileLength present) or laid out in
+        // full-width strips (RowsPerStrip, with StripOffsets/StripByteCounts in place of
+        // TileOffsets/TileByteCounts). GDAL always writes tiled COGs, but plain TIFFs - and COGs
+        // produced by older tools - can still use strips, so both are supported here by modeling
+        // a strip as a single column of tile_width=width blocks.
+        let (tile_width, tile_height, is_striped) =
+            match ifd.get_u64_tag_value(source, IFDTag::TileWidth).await {
+                Ok(tile_width) => {
+                    let tile_height = ifd.get_u64_tag_value(source, IFDTag::TileLength).await?;
+                    (tile_width, tile_height, false)
+                }
+                Err(Error::RequiredTagNotFound(_)) => {
+                    let rows_per_strip =
+                        ifd.get_u64_tag_value(source, IFDTag::RowsPerStrip).await?;
+                    (width, rows_per_strip, true)
+                }
+                Err(e) => return Err(e),
+            };
 
         Ok(Overview {
-            width: ifd.get_u64_tag_value(source, IFDTag::ImageWidth).await?,
-            height: ifd.get_u64_tag_value(source, IFDTag::ImageLength).await?,
+            width,
+            height,
             bands,
             photometric_interpretation,
-            tile_width: ifd.get_u64_tag_value(source, IFDTag::TileWidth).await?,
-            tile_height: ifd.get_u64_tag_value(source, IFDTag::TileLength).await?,
+            color_map,
+            tile_width,
+            tile_height,
             ifd,
             is_full_resolution,
             compression,
+            predictor,
+            is_striped,
+            is_planar,
             data_type,
+            reference_black_white,
         })
     }
 
     pub async fn make_reader(&self, source: &mut Source) -> Result<OverviewDataReader, Error> {
         // Note that as per the COG spec, those two arrays are likely *not* stored compactly next
         // to the header, so this will cause additional reads to the source
-        let tile_offsets = self
-            .ifd
-            .get_vec_u64_tag_value(source, IFDTag::TileOffsets)
-            .await?;
+        let (offsets_tag, byte_counts_tag) = if self.is_striped {
+            (IFDTag::StripOffsets, IFDTag::StripByteCounts)
+        } else {
+            (IFDTag::TileOffsets, IFDTag::TileByteCounts)
+        };
+        let tile_offsets = self.ifd.get_vec_u64_tag_value(source, offsets_tag).await?;
         let tile_bytes_counts = self
             .ifd
-            .get_vec_u64_tag_value(source, IFDTag::TileByteCounts)
+            .get_vec_u64_tag_value(source, byte_counts_tag)
             .await?;
         Ok(OverviewDataReader {
             width: self.width,
@@ -252,7 +494,11 @@ impl Overview {
             tile_offsets,
             tile_bytes_counts,
             compression: self.compression.clone(),
+            predictor: self.predictor,
+            is_striped: self.is_striped,
+            is_planar: self.is_planar,
             data_type: self.data_type,
+            merge_gap: DEFAULT_TILE_MERGE_GAP_BYTES,
         })
     }
 }
@@ -276,18 +522,74 @@ impl ImageRect {
     }
 }
 
+// A tile whose compressed bytes still need to be fetched from `source` to satisfy a
+// `read_image_part` call.
+struct NeededTile {
+    tile_i: u64,
+    tile_j: u64,
+    // The plane this tile's bytes belong to for a PlanarConfiguration=2 overview (always 0 for
+    // PlanarConfiguration=1, where every band is interleaved into the same tile).
+    band: usize,
+    offset: u64,
+    byte_count: u64,
+}
+
+// A contiguous (or near-contiguous, within the merge gap) span of the source covering one or more
+// `NeededTile`s, fetched with a single `read_exact_direct` call.
+struct MergedRange {
+    offset: u64,
+    len: u64,
+    tiles: Vec<NeededTile>,
+}
+
+// Sorts `tiles` by byte offset and merges any whose ranges are contiguous or within `merge_gap`
+// bytes of each other, so the caller issues one read per merged range instead of one per tile.
+fn coalesce_tile_reads(mut tiles: Vec<NeededTile>, merge_gap: u64) -> Vec<MergedRange> {
+    tiles.sort_by_key(|t| t.offset);
+    let mut ranges: Vec<MergedRange> = vec![];
+    for tile in tiles {
+        let tile_end = tile.offset + tile.byte_count;
+        match ranges.last_mut() {
+            Some(range) if tile.offset <= range.offset + range.len + merge_gap => {
+                range.len = tile_end.max(range.offset + range.len) - range.offset;
+                range.tiles.push(tile);
+            }
+            _ => ranges.push(MergedRange {
+                offset: tile.offset,
+                len: tile.byte_count,
+                tiles: vec![tile],
+            }),
+        }
+    }
+    ranges
+}
+
 impl OverviewDataReader {
+    /// Sets the gap (in bytes) below which two tiles' byte ranges get coalesced into a single
+    /// read. Same-region callers reading many adjacent tiles can raise this to cut GETs against a
+    /// remote source at the cost of some wasted bandwidth; bandwidth-constrained callers can set
+    /// it to 0 to fetch exactly the bytes each tile needs.
+    pub fn set_merge_gap(&mut self, merge_gap: u64) {
+        self.merge_gap = merge_gap;
+    }
+
     // Pastes the given tile at the right location in the output array. Both tile_rect and out_rect
-    // define the area covered by out/tile in the whole image
-    // Assumes both out_data and tile_data are packed as HwC (PlanarConfiguration=1)
+    // define the area covered by out/tile in the whole image.
+    //
+    // `out_data` is always packed as HwC (interleaved bands). `tile_data` is too, *unless* `band`
+    // is `Some` - which happens for a PlanarConfiguration=2 overview, where each tile/strip only
+    // holds one band's worth of samples (so `tile_data` is effectively HwC with a single channel)
+    // and needs scattering into just that one output channel instead of being copied wholesale.
     fn paste_tile(
         &self,
         out_data: &mut [u8],
         tile_data: &[u8],
         out_rect: &ImageRect,
         tile_rect: &ImageRect,
+        band: Option<usize>,
     ) {
         let size_bytes = self.data_type.unpacked_type().size_bytes();
+        let tile_nbands = if band.is_some() { 1 } else { self.bands.nbands };
         // Note that tiles can be larger than the image, so we need to ignore out of bounds pixels
         for ti in tile_rect.i_from..tile_rect.i_to {
             if ti < out_rect.i_from || ti >= out_rect.i_to {
@@ -297,21 +599,33 @@ impl OverviewDataReader {
                 if tj < out_rect.j_from || tj >= out_rect.j_to {
                     continue;
                 }
-                let bytes_to_copy = self.bands.nbands * size_bytes;
-                let out_offset = ((ti - out_rect.i_from)
+                let out_pixel_offset = ((ti - out_rect.i_from)
                     * out_rect.width()
                     * self.bands.nbands as u64
                     * size_bytes as u64
                     + (tj - out_rect.j_from) * self.bands.nbands as u64 * size_bytes as u64)
                     as usize;
-                let tile_offset = ((ti - tile_rect.i_from)
+                let tile_pixel_offset = ((ti - tile_rect.i_from)
                     * self.tile_width
-                    * self.bands.nbands as u64
+                    * tile_nbands as u64
                     * size_bytes as u64
-                    + (tj - tile_rect.j_from) * self.bands.nbands as u64 * size_bytes as u64)
+                    + (tj - tile_rect.j_from) * tile_nbands as u64 * size_bytes as u64)
                     as usize;
-                out_data[out_offset..(out_offset + bytes_to_copy)]
-                    .copy_from_slice(&tile_data[tile_offset..(tile_offset + bytes_to_copy)]);
+                match band {
+                    Some(band) => {
+                        let out_offset = out_pixel_offset + band * size_bytes;
+                        out_data[out_offset..(out_offset + size_bytes)].copy_from_slice(
+                            &tile_data[tile_pixel_offset..(tile_pixel_offset + size_bytes)],
+                        );
+                    }
+                    None => {
+                        let bytes_to_copy = self.bands.nbands * size_bytes;
+                        out_data[out_pixel_offset..(out_pixel_offset + bytes_to_copy)]
+                            .copy_from_slice(
+                                &tile_data[tile_pixel_offset..(tile_pixel_offset + bytes_to_copy)],
+                            );
+                    }
+                }
             }
         }
     }
@@ -321,6 +635,37 @@ impl OverviewDataReader {
         source: &mut Source,
         rect: &ImageRect,
     ) -> Result<ImageBuffer, Error> {
+        let mut out_data = vec![];
+        let mut scratch = TileScratch::new();
+        self.read_image_part_into(source, rect, &mut out_data, &mut scratch)
+            .await?;
+        Ok(ImageBuffer {
+            width: rect.width() as usize,
+            height: rect.height() as usize,
+            nbands: self.bands.nbands,
+            data_type: self.data_type.unpacked_type(),
+            has_alpha: self.bands.has_alpha,
+            nodata: None,
+            data: out_data,
+        })
+    }
+
+    /// Same as `read_image_part`, but writes the decoded pixels into a caller-provided `out`
+    /// buffer and reuses `scratch`'s compressed/decompressed tile buffers across the tile loop,
+    /// instead of allocating fresh `Vec<u8>`s for each. A tile-server loop that keeps its own
+    /// `out`/`TileScratch` around across many overlapping reads can amortize allocations to near
+    /// zero this way.
+    ///
+    /// `out` is cleared and resized (not reallocated, as long as its capacity is already
+    /// sufficient) to fit `rect`; it is zero-initialized so that sparse (all-nodata) tiles, which
+    /// the COG spec allows omitting entirely, are left as zeros without needing an explicit write.
+    pub async fn read_image_part_into(
+        &self,
+        source: &mut Source,
+        rect: &ImageRect,
+        out: &mut Vec<u8>,
+        scratch: &mut TileScratch,
+    ) -> Result<(), Error> {
         if rect.j_to > self.width {
             return Err(Error::OutOfBoundsRead(format!(
                 "rect.j_to out of bounds: {} > {}",
@@ -333,57 +678,133 @@ impl OverviewDataReader {
                 rect.i_to, self.height
             )));
         }
-        // TODO: May want the caller to pass the output vector instead of allocating
-        println!(
-            "tile_width={}, tile_height={}, bands={:?}, data_type={:?}",
-            self.tile_width, self.tile_height, self.bands, self.data_type
-        );
-        let mut out_data = {
-            let nbytes = rect.width() as usize
-                * rect.height() as usize
-                * self.bands.nbands
-                * self.data_type.unpacked_type().size_bytes();
-            vec![0u8; nbytes]
-        };
+        let nbytes = rect.width() as usize
+            * rect.height() as usize
+            * self.bands.nbands
+            * self.data_type.unpacked_type().size_bytes();
+        out.clear();
+        out.resize(nbytes, 0u8);
         let start_tile_j = rect.j_from / self.tile_width;
         let start_tile_i = rect.i_from / self.tile_height;
         let end_tile_j = (rect.j_to as f64 / self.tile_width as f64).ceil() as u64;
         let end_tile_i = (rect.i_to as f64 / self.tile_height as f64).ceil() as u64;
 
         let tiles_across = self.width.div_ceil(self.tile_width);
+        let tiles_down = self.height.div_ceil(self.tile_height);
+        // For PlanarConfiguration=2, TileOffsets/StripOffsets holds one full plane's worth of
+        // blocks per band, band-major: plane 0's blocks (in the usual left-to-right,
+        // top-to-bottom order) come first, then plane 1's, and so on.
+        let blocks_per_band = tiles_across * tiles_down;
+        let bands_to_fetch: Vec<usize> = if self.is_planar {
+            (0..self.bands.nbands).collect()
+        } else {
+            vec![0]
+        };
+
+        let mut needed_tiles = vec![];
+        for &band in &bands_to_fetch {
+            for tile_i in start_tile_i..end_tile_i {
+                for tile_j in start_tile_j..end_tile_j {
+                    // As per the spec, tiles are ordered left to right and top to bottom
+                    let spatial_index = tile_i * tiles_across + tile_j;
+                    let tile_index = if self.is_planar {
+                        band as u64 * blocks_per_band + spatial_index
+                    } else {
+                        spatial_index
+                    };
+                    let offset = self.tile_offsets[tile_index as usize];
+                    let byte_count = self.tile_bytes_counts[tile_index as usize];
+                    // A tile with offset=0 and byte_count=0 is "sparse": the spec allows omitting
+                    // tiles that are entirely nodata instead of storing them. `out_data` is
+                    // already zero-initialized, which is exactly the nodata fill value, so
+                    // there's nothing to read or paste here.
+                    if offset == 0 && byte_count == 0 {
+                        continue;
+                    }
+                    needed_tiles.push(NeededTile {
+                        tile_i,
+                        tile_j,
+                        band,
+                        offset,
+                        byte_count,
+                    });
+                }
+            }
+        }
+
+        // Fetch the tiles one merged range at a time instead of one `read_exact_direct` per tile,
+        // to cut down on round-trips against remote/object-store sources.
+        for range in coalesce_tile_reads(needed_tiles, self.merge_gap) {
+            scratch.compressed.clear();
+            scratch.compressed.resize(range.len as usize, 0u8);
+            source
+                .read_exact_direct(range.offset, &mut scratch.compressed)
+                .await?;
+
+            for tile in range.tiles {
+                let start = (tile.offset - range.offset) as usize;
+                let end = start + tile.byte_count as usize;
+
+                let tile_rect = ImageRect {
+                    i_from: tile.tile_i * self.tile_height,
+                    j_from: tile.tile_j * self.tile_width,
+                    // Tile edge blocks are padded out to the full tile grid by the spec, but a
+                    // strip's last block just has fewer rows (no padding), so it needs clipping
+                    // to the image height instead.
+                    i_to: if self.is_striped {
+                        ((tile.tile_i + 1) * self.tile_height).min(self.height)
+                    } else {
+                        (tile.tile_i + 1) * self.tile_height
+                    },
+                    j_to: (tile.tile_j + 1) * self.tile_width,
+                };
+                let tile_rows = tile_rect.height() as usize;
+                // A PlanarConfiguration=2 tile only holds one band's worth of samples rather than
+                // all of them interleaved together.
+                let samples_per_pixel = if self.is_planar { 1 } else { self.bands.nbands };
 
-        // The below code assumes PlanarConfiguration=1 which is what GDAL does when creating COG, although
-        // COGs with other planar configurations are possible in theory
-        for tile_i in start_tile_i..end_tile_i {
-            for tile_j in start_tile_j..end_tile_j {
-                // As per the spec, tiles are ordered left to right and top to bottom
-                let tile_index = tile_i * tiles_across + tile_j;
-                let offset = self.tile_offsets[tile_index as usize];
+                // Rows are individually padded to a byte boundary, so for sub-byte sample depths
+                // the packed tile size isn't simply width*height*nbands*bits/8. `tile_rows` (not
+                // the nominal `self.tile_height`) accounts for a strip's possibly-shorter last
+                // block.
+                let expected_packed_tile_bytes =
+                    (self.tile_width as usize * samples_per_pixel * self.data_type.bit_depth())
+                        .div_ceil(8)
+                        * tile_rows;
                 // Read compressed buf
-                let mut tile_data = vec![0u8; self.tile_bytes_counts[tile_index as usize] as usize];
-                // We use read_direct here to read the whole tile at once
-                // TODO: Can this lead to too huge request depending on tile size ? Or does COG always
-                // guarantee reasonable tile size ?
-                source.read_exact_direct(offset, &mut tile_data).await?;
-
-                // Decompress
-                // TODO: Could reduce allocations by reusing the output vector across tiles (e.g. weezl support into_vec)
-                tile_data = self.compression.decompress(
-                    tile_data,
+                self.compression.decompress_into(
+                    &scratch.compressed[start..end],
+                    self.tile_width as usize,
+                    tile_rows,
+                    expected_packed_tile_bytes,
+                    &mut scratch.decompressed,
+                )?;
+                // The predictor is a separate delta filter layered on top of the compression codec,
+                // so it needs to be reversed on the decompressed-but-still-packed bytes before
+                // `unpack_bytes` below. `reconstruct` takes/returns its buffer by value, so take it
+                // out of `scratch` rather than cloning it.
+                let predicted = self.predictor.reconstruct(
+                    std::mem::take(&mut scratch.decompressed),
                     self.tile_width as usize,
-                    self.tile_height as usize,
+                    samples_per_pixel,
+                    self.data_type.bit_depth(),
                 )?;
-                tile_data = self.data_type.unpack_bytes(&tile_data);
+                if predicted.len() != expected_packed_tile_bytes {
+                    return Err(Error::InvalidData(format!(
+                        "packed tile data has unexpected length. {} instead of {}. Is there some decompression issue ?",
+                        predicted.len(), expected_packed_tile_bytes
+                    )));
+                }
+                let tile_data = self.data_type.unpack_bytes(
+                    &predicted,
+                    self.tile_width as usize,
+                    samples_per_pixel,
+                );
+                scratch.decompressed = predicted;
 
-                let tile_rect = ImageRect {
-                    i_from: tile_i * self.tile_height,
-                    j_from: tile_j * self.tile_width,
-                    i_to: (tile_i + 1) * self.tile_height,
-                    j_to: (tile_j + 1) * self.tile_width,
-                };
                 let tile_data_expected_nbytes = tile_rect.width()
                     * tile_rect.height()
-                    * self.bands.nbands as u64
+                    * samples_per_pixel as u64
                     * self.data_type.unpacked_type().size_bytes() as u64;
                 if tile_data.len() as u64 != tile_data_expected_nbytes {
                     // If we fail here, two things could have happened:
@@ -397,17 +818,15 @@ impl OverviewDataReader {
                         tile_data.len(), tile_data_expected_nbytes
                     )));
                 }
-                self.paste_tile(&mut out_data, &tile_data, rect, &tile_rect);
+                let band = if self.is_planar {
+                    Some(tile.band)
+                } else {
+                    None
+                };
+                self.paste_tile(out, &tile_data, rect, &tile_rect, band);
             }
         }
-        Ok(ImageBuffer {
-            width: rect.width() as usize,
-            height: rect.height() as usize,
-            nbands: self.bands.nbands,
-            data_type: self.data_type.unpacked_type(),
-            has_alpha: self.bands.has_alpha,
-            data: out_data,
-        })
+        Ok(())
     }
 }
 
@@ -425,7 +844,11 @@ impl COG {
             match photo_interp {
                 interp @ (PhotometricInterpretation::Rgb
                 | PhotometricInterpretation::YCbCr
-                | PhotometricInterpretation::BlackIsZero) => {
+                | PhotometricInterpretation::BlackIsZero
+                | PhotometricInterpretation::WhiteIsZero
+                | PhotometricInterpretation::Palette
+                | PhotometricInterpretation::Separated
+                | PhotometricInterpretation::CIELab) => {
                     overviews.push(Overview::from_ifd(ifd, &mut source, interp).await?);
                 }
                 interp @ PhotometricInterpretation::Mask => {
@@ -494,6 +917,17 @@ impl COG {
         })
     }
 
+    /// Writes `image`/`georeference` out to `path` as a Cloud Optimized GeoTIFF. This is the
+    /// write-side counterpart to `open`; see `WriteOptions` for what's configurable.
+    pub async fn write(
+        path: &str,
+        image: &ImageBuffer,
+        georeference: &Georeference,
+        options: &super::writer::WriteOptions,
+    ) -> Result<(), Error> {
+        super::writer::write(path, image, georeference, options).await
+    }
+
     pub fn width(&self) -> u64 {
         self.overviews[0].width
     }
@@ -508,14 +942,17 @@ impl COG {
 
     pub fn compute_georeference_for_overview(&self, overview: &Overview) -> Georeference {
         let scale_factor = overview.width as f64 / self.width() as f64;
+        let geo_transform = &self.georeference.geo_transform;
         Georeference {
-            crs: self.georeference.crs,
+            crs: self.georeference.crs.clone(),
             unit: self.georeference.unit,
             geo_transform: Geotransform {
-                ul_x: self.georeference.geo_transform.ul_x,
-                ul_y: self.georeference.geo_transform.ul_y,
-                x_res: self.georeference.geo_transform.x_res / scale_factor,
-                y_res: self.georeference.geo_transform.y_res / scale_factor,
+                ul_x: geo_transform.ul_x,
+                ul_y: geo_transform.ul_y,
+                x_res: geo_transform.x_res / scale_factor,
+                y_res: geo_transform.y_res / scale_factor,
+                x_rotation: geo_transform.x_rotation / scale_factor,
+                y_rotation: geo_transform.y_rotation / scale_factor,
             },
         }
     }
@@ -526,14 +963,13 @@ impl COG {
     }
 
     pub fn lnglat_bounds(&self) -> Result<BoundingBox, Error> {
-        let transform = Transform::new(self.georeference.crs.epsg_code(), 4326)?;
-
-        let x1 = self.georeference.geo_transform.ul_x;
-        let x2 = self.georeference.geo_transform.ul_x
-            + self.georeference.geo_transform.x_res * self.width() as f64;
-        let y1 = self.georeference.geo_transform.ul_y;
-        let y2 = self.georeference.geo_transform.ul_y
-            + self.georeference.geo_transform.y_res * self.height() as f64;
+        let transform = Transform::new(&self.georeference.crs.proj_spec()?, "EPSG:4326")?;
+
+        let geo_transform = &self.georeference.geo_transform;
+        let x1 = geo_transform.ul_x;
+        let x2 = geo_transform.ul_x + geo_transform.x_res * self.width() as f64;
+        let y1 = geo_transform.ul_y;
+        let y2 = geo_transform.ul_y + geo_transform.y_res * self.height() as f64;
         let xmin = x1.min(x2);
         let xmax = x1.max(x2);
         let ymin = y1.min(y2);
@@ -554,6 +990,96 @@ impl COG {
             .map_err(|e| e.into())
     }
 
+    /// Inspects this already-opened COG and reports whether it's a well-formed, tiling-friendly
+    /// Cloud Optimized GeoTIFF, in the spirit of rio-cogeo's "Is it a COG" check. `COG::open`
+    /// already hard-fails on the handful of requirements it relies on (tiled, ordered,
+    /// single-image overviews - see the checks right after reading `ifds` there); this instead
+    /// collects a fuller report - including things that are merely suboptimal, like an unusual
+    /// block size - without refusing to open the file.
+    pub async fn validate(&mut self) -> Result<COGValidationReport, Error> {
+        let mut report = COGValidationReport::default();
+
+        // COG spec recommendation: IFDs live near the start of the file in increasing offset
+        // order, so a client can fetch the whole header with one small leading range read instead
+        // of one read per IFD.
+        for i in 1..self.overviews.len() {
+            if self.overviews[i].ifd.offset <= self.overviews[i - 1].ifd.offset {
+                report.warnings.push(format!(
+                    "overview {}'s IFD (offset={}) is not laid out after overview {}'s (offset={}) - \
+                     the header can't be fetched with a single small leading read",
+                    i, self.overviews[i].ifd.offset, i - 1, self.overviews[i - 1].ifd.offset
+                ));
+            }
+        }
+
+        if self.overviews.len() < 2 {
+            report.warnings.push(
+                "no reduced-resolution overviews besides the full-resolution image - low zoom \
+                 tiles will require reading and downsampling the whole image"
+                    .to_string(),
+            );
+        }
+
+        for (i, overview) in self.overviews.iter().enumerate() {
+            report.block_sizes.push((overview.tile_width, overview.tile_height));
+            if !overview.tile_width.is_power_of_two() || !overview.tile_height.is_power_of_two() {
+                report.warnings.push(format!(
+                    "overview {}: block size {}x{} is not a power of two",
+                    i, overview.tile_width, overview.tile_height
+                ));
+            } else if overview.tile_width != 256 && overview.tile_width != 512 {
+                report.warnings.push(format!(
+                    "overview {}: block size {}x{} is a power of two but not the usual 256 or 512",
+                    i, overview.tile_width, overview.tile_height
+                ));
+            }
+
+            if i > 0 {
+                let decimation = self.overviews[i - 1].width as f64 / overview.width as f64;
+                report.overview_decimations.push(decimation);
+                if (decimation.log2().round() - decimation.log2()).abs() > 0.1 {
+                    report.warnings.push(format!(
+                        "overview {}: decimation factor {:.2} relative to overview {} is not close \
+                         to a power of two",
+                        i, decimation, i - 1
+                    ));
+                }
+            }
+
+            // A PlanarConfiguration=2 overview stores one full plane of blocks per band.
+            let planes = if overview.is_planar {
+                overview.bands.nbands as u64
+            } else {
+                1
+            };
+            let expected_tiles = planes
+                * overview.width.div_ceil(overview.tile_width)
+                * overview.height.div_ceil(overview.tile_height);
+            let reader = overview.make_reader(&mut self.source).await?;
+            if reader.tile_offsets.len() as u64 != expected_tiles {
+                report.errors.push(format!(
+                    "overview {}: expected {} tiles ({}x{} blocks, {} plane(s)) but TileOffsets has {} entries",
+                    i,
+                    expected_tiles,
+                    overview.width.div_ceil(overview.tile_width),
+                    overview.height.div_ceil(overview.tile_height),
+                    planes,
+                    reader.tile_offsets.len()
+                ));
+            }
+            if reader.tile_offsets.len() != reader.tile_bytes_counts.len() {
+                report.errors.push(format!(
+                    "overview {}: TileOffsets has {} entries but TileByteCounts has {}",
+                    i,
+                    reader.tile_offsets.len(),
+                    reader.tile_bytes_counts.len()
+                ));
+            }
+        }
+
+        Ok(report)
+    }
+
     pub async fn make_reader(&mut self, overview_index: usize) -> Result<COGDataReader, Error> {
         let overview_reader = self.overviews[overview_index]
             .make_reader(&mut self.source)
@@ -596,6 +1122,87 @@ impl COG {
             .read_image_part(&mut self.source, rect)
             .await
     }
+
+    /// Picks the coarsest overview whose resolution is still finer-or-equal to
+    /// `target_x_res`/`target_y_res`, the way GDAL chooses an overview level for `RasterIO`.
+    /// Falls back to the full resolution image (index 0) if every overview is coarser than
+    /// requested.
+    fn pick_overview_for_resolution(&self, target_x_res: f64, target_y_res: f64) -> usize {
+        let mut selected_index = 0;
+        let mut selected_x_res = self.georeference_x_y_res(&self.overviews[0]).0;
+        for (i, overview) in self.overviews.iter().enumerate() {
+            let (x_res, y_res) = self.georeference_x_y_res(overview);
+            if x_res <= target_x_res && y_res <= target_y_res && x_res >= selected_x_res {
+                selected_index = i;
+                selected_x_res = x_res;
+            }
+        }
+        selected_index
+    }
+
+    fn georeference_x_y_res(&self, overview: &Overview) -> (f64, f64) {
+        let geo_transform = self.compute_georeference_for_overview(overview).geo_transform;
+        (geo_transform.x_res.abs(), geo_transform.y_res.abs())
+    }
+
+    /// Reads the area of this COG covered by `bounds_in_crs` (in the COG's own CRS) and resamples
+    /// it to the exact pixel grid implied by `target_x_res`/`target_y_res`.
+    ///
+    /// This mirrors how GDAL's `RasterIO` picks an overview and applies a resampling kernel: (1)
+    /// the coarsest overview whose native resolution is still finer-or-equal to the requested one
+    /// is selected via `pick_overview_for_resolution`, (2) `bounds_in_crs` is mapped to that
+    /// overview's pixel grid and read, and (3) the result is resampled down to the requested grid
+    /// with `resample`.
+    pub async fn read_region_at_resolution(
+        &mut self,
+        bounds_in_crs: &BoundingBox,
+        target_x_res: f64,
+        target_y_res: f64,
+        resample: ResampleAlg,
+    ) -> Result<ImageBuffer, Error> {
+        let overview_index = self.pick_overview_for_resolution(target_x_res, target_y_res);
+        let overview = &self.overviews[overview_index];
+        // Rotated/sheared rasters are rejected at `COG::open` time, so `world_to_pixel` below is a
+        // plain axis-aligned scale+offset (never singular).
+        let geo_transform = self.compute_georeference_for_overview(overview).geo_transform;
+
+        let ul = geo_transform
+            .world_to_pixel(Vec2f {
+                x: bounds_in_crs.xmin,
+                y: bounds_in_crs.ymax,
+            })
+            .expect("axis-aligned geo_transform is never singular");
+        let lr = geo_transform
+            .world_to_pixel(Vec2f {
+                x: bounds_in_crs.xmax,
+                y: bounds_in_crs.ymin,
+            })
+            .expect("axis-aligned geo_transform is never singular");
+        let rect = ImageRect {
+            j_from: (ul.x.floor() as i64).clamp(0, overview.width as i64) as u64,
+            j_to: (lr.x.ceil() as i64).clamp(0, overview.width as i64) as u64,
+            i_from: (ul.y.floor() as i64).clamp(0, overview.height as i64) as u64,
+            i_to: (lr.y.ceil() as i64).clamp(0, overview.height as i64) as u64,
+        };
+        if rect.j_to <= rect.j_from || rect.i_to <= rect.i_from {
+            return Err(Error::OutOfBoundsRead(format!(
+                "bounds_in_crs {:?} don't intersect the COG",
+                bounds_in_crs
+            )));
+        }
+
+        let image = self.read_image_part(overview_index, &rect).await?;
+
+        let target_width = ((bounds_in_crs.xmax - bounds_in_crs.xmin) / target_x_res).round() as usize;
+        let target_height = ((bounds_in_crs.ymax - bounds_in_crs.ymin) / target_y_res).round() as usize;
+        if target_width == 0 || target_height == 0 {
+            return Err(Error::OutOfBoundsRead(format!(
+                "target resolution too coarse for bounds_in_crs {:?}",
+                bounds_in_crs
+            )));
+        }
+        Ok(image.resample_to(target_width, target_height, resample))
+    }
 }
 
 // A helper class wraping an overview reader and a potential mask overview reader. This is
@@ -1,12 +1,22 @@
 use super::Error;
 use flate2::bufread::DeflateDecoder;
-use std::io::Read;
+use flate2::write::ZlibEncoder;
+use flate2::Compression as Flate2Level;
+use std::io::{Read, Write};
 
-pub fn decompress_deflate(data: Vec<u8>) -> Result<Vec<u8>, Error> {
+/// Decompress into a caller-owned buffer, which is cleared (keeping its capacity) rather than
+/// reallocated - lets callers decoding many tiles hoist one scratch buffer out of their loop.
+pub fn decompress_deflate_into(data: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
     // As per the Adobe deflate documentation, the compressed data should start with a header:
     // https://www.awaresystems.be/imaging/tiff/specification/TIFFphotoshop.pdf
     // Or section 2.2 of the zlib RFC
     // https://www.rfc-editor.org/rfc/rfc1950
+    if data.len() < 2 {
+        return Err(Error::DecompressionError(format!(
+            "Deflate tile too short to hold a zlib header: got {} bytes",
+            data.len()
+        )));
+    }
     let header = &data[0..2];
     if header[0] & 0xF != 8 {
         return Err(Error::DecompressionError(format!(
@@ -14,13 +24,32 @@ pub fn decompress_deflate(data: Vec<u8>) -> Result<Vec<u8>, Error> {
             header,
         )));
     }
+    out.clear();
     let mut decoder = DeflateDecoder::new(&data[2..]);
-    let mut out: Vec<u8> = vec![];
-    match decoder.read_to_end(&mut out) {
-        Ok(_nbytes) => Ok(out),
+    match decoder.read_to_end(out) {
+        Ok(_nbytes) => Ok(()),
         Err(e) => Err(Error::DecompressionError(format!(
             "decompression error: {}",
             e
         ))),
     }
 }
+
+pub fn decompress_deflate(data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    decompress_deflate_into(&data, &mut out)?;
+    Ok(out)
+}
+
+/// Compresses `data` with the same Adobe/zlib-wrapped deflate stream `decompress_deflate_into`
+/// expects (2-byte zlib header, then the raw deflate stream - the trailing adler32 checksum zlib
+/// also appends is harmless since `DeflateDecoder` stops at the deflate end-of-stream marker).
+pub fn compress_deflate(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Flate2Level::default());
+    encoder
+        .write_all(data)
+        .map_err(|e| Error::OtherError(format!("deflate compression error: {}", e)))?;
+    encoder
+        .finish()
+        .map_err(|e| Error::OtherError(format!("deflate compression error: {}", e)))
+}
@@ -0,0 +1,224 @@
+// JPEG 2000 (TIFF compression tag 34712) tiles, decoded via OpenJPEG
+// (https://github.com/uclouvain/openjpeg) through the `openjpeg-sys` bindings, statically linked
+// so this doesn't add a runtime shared-library dependency.
+//
+// Unlike `jpeg::Decompressor`, OpenJPEG lets the caller restrict decoding to a sub-rectangle of
+// the image (`opj_set_decode_area`) and to a lower-resolution DWT level (the decoder's
+// `cp_reduce` parameter - one reduction step halves both dimensions, the same idea as a COG
+// overview). `find_best_overview` already picks an overview per zoom level for the *tile grid*,
+// but a JP2K-compressed overview can still be asked to decode at an even coarser internal
+// resolution than its stored one - passing a nonzero reduction factor avoids inverse-DWT work the
+// caller is just going to throw away by downsampling again.
+//
+// References:
+// - OpenJPEG's own decompress example wires up exactly this stream/area/reduction dance:
+//   https://github.com/uclouvain/openjpeg/blob/master/src/bin/jp2/opj_decompress.c
+// - GDAL's JP2OpenJPEG driver does the windowed decode for the same reason acog wants it here:
+//   https://github.com/OSGeo/gdal/blob/master/frmts/jp2openjpeg/openjpegdataset.cpp
+
+use crate::Error;
+use openjpeg_sys as opj;
+use std::io::Cursor;
+use std::os::raw::c_void;
+
+#[derive(Clone)]
+pub struct Decompressor {
+    nbands: usize,
+    bit_depth: usize,
+    // DWT resolution reduction factor: 0 decodes at full resolution, 1 halves both dimensions,
+    // etc. - see the module doc comment.
+    reduction_factor: u32,
+}
+
+// Bridges a `Cursor<&[u8]>` to the read/skip/seek callbacks `opj_stream_t` expects. OpenJPEG's
+// public C API has no memory-stream constructor (only `opj_stream_create_default_file_stream`),
+// so every in-memory consumer has to wire this up itself - this is the same shape as the stream
+// openjpeg's own `opj_decompress.c` sample builds around a `FILE*`.
+struct MemoryStream<'a> {
+    cursor: Cursor<&'a [u8]>,
+}
+
+extern "C" fn read_fn(buf: *mut c_void, bytes: opj::OPJ_SIZE_T, user_data: *mut c_void) -> opj::OPJ_SIZE_T {
+    use std::io::Read;
+    let stream = unsafe { &mut *(user_data as *mut MemoryStream) };
+    let out = unsafe { std::slice::from_raw_parts_mut(buf as *mut u8, bytes as usize) };
+    match stream.cursor.read(out) {
+        Ok(0) => opj::OPJ_SIZE_T::MAX, // OpenJPEG's convention for EOF on a read callback
+        Ok(n) => n as opj::OPJ_SIZE_T,
+        Err(_) => opj::OPJ_SIZE_T::MAX,
+    }
+}
+
+extern "C" fn skip_fn(bytes: opj::OPJ_OFF_T, user_data: *mut c_void) -> opj::OPJ_OFF_T {
+    let stream = unsafe { &mut *(user_data as *mut MemoryStream) };
+    let pos = stream.cursor.position() as i64 + bytes;
+    if pos < 0 {
+        return -1;
+    }
+    stream.cursor.set_position(pos as u64);
+    bytes
+}
+
+extern "C" fn seek_fn(bytes: opj::OPJ_OFF_T, user_data: *mut c_void) -> opj::OPJ_BOOL {
+    let stream = unsafe { &mut *(user_data as *mut MemoryStream) };
+    if bytes < 0 {
+        return 0;
+    }
+    stream.cursor.set_position(bytes as u64);
+    1
+}
+
+impl Decompressor {
+    pub fn new(nbands: usize, bit_depth: usize) -> Result<Decompressor, Error> {
+        Ok(Decompressor {
+            nbands,
+            bit_depth,
+            reduction_factor: 0,
+        })
+    }
+
+    /// Restricts subsequent `decompress` calls to this many fewer DWT resolution levels - see the
+    /// module doc comment. Only meaningful when reading an overview whose *stored* resolution is
+    /// still finer than what the caller actually needs.
+    pub fn with_reduction_factor(mut self, reduction_factor: u32) -> Decompressor {
+        self.reduction_factor = reduction_factor;
+        self
+    }
+
+    /// Decodes one tile's JPEG 2000 codestream into this crate's interleaved, row-major
+    /// `ImageBuffer` byte layout. `width`/`height` are the tile's nominal dimensions, used only to
+    /// validate the decoded image size - OpenJPEG gets the authoritative size from the codestream
+    /// header itself.
+    pub fn decompress(&self, data: Vec<u8>, width: usize, height: usize) -> Result<Vec<u8>, Error> {
+        let mut mem_stream = MemoryStream {
+            cursor: Cursor::new(data.as_slice()),
+        };
+
+        unsafe {
+            let stream = opj::opj_stream_create(opj::OPJ_J2K_STREAM_CHUNK_SIZE as usize, 1);
+            if stream.is_null() {
+                return Err(Error::DecompressionError(
+                    "JP2K: failed to create OpenJPEG stream".to_string(),
+                ));
+            }
+            opj::opj_stream_set_read_function(stream, Some(read_fn));
+            opj::opj_stream_set_skip_function(stream, Some(skip_fn));
+            opj::opj_stream_set_seek_function(stream, Some(seek_fn));
+            opj::opj_stream_set_user_data(
+                stream,
+                &mut mem_stream as *mut MemoryStream as *mut c_void,
+                None,
+            );
+            opj::opj_stream_set_user_data_length(stream, data.len() as opj::OPJ_UINT64);
+
+            let codec = opj::opj_create_decompress(opj::OPJ_CODEC_FORMAT::OPJ_CODEC_J2K);
+            if codec.is_null() {
+                opj::opj_stream_destroy(stream);
+                return Err(Error::DecompressionError(
+                    "JP2K: failed to create OpenJPEG decompressor".to_string(),
+                ));
+            }
+
+            let mut params: opj::opj_dparameters_t = std::mem::zeroed();
+            opj::opj_set_default_decoder_parameters(&mut params);
+            params.cp_reduce = self.reduction_factor;
+
+            if opj::opj_setup_decoder(codec, &mut params) == 0 {
+                opj::opj_destroy_codec(codec);
+                opj::opj_stream_destroy(stream);
+                return Err(Error::DecompressionError(
+                    "JP2K: opj_setup_decoder failed".to_string(),
+                ));
+            }
+
+            let mut image: *mut opj::opj_image_t = std::ptr::null_mut();
+            if opj::opj_read_header(stream, codec, &mut image) == 0 || image.is_null() {
+                opj::opj_destroy_codec(codec);
+                opj::opj_stream_destroy(stream);
+                return Err(Error::DecompressionError(
+                    "JP2K: opj_read_header failed".to_string(),
+                ));
+            }
+
+            // This tile's whole extent, at whatever resolution `reduction_factor` leaves it at -
+            // the window is degenerate here because a COG tile is already the unit a caller asks
+            // for; `opj_set_decode_area` earns its keep combined with `cp_reduce` above, letting an
+            // overview request skip inverse-DWT work for resolution levels it would just downsample
+            // away again.
+            let full_width = (*image).x1 - (*image).x0;
+            let full_height = (*image).y1 - (*image).y0;
+            if opj::opj_set_decode_area(codec, image, 0, 0, full_width as i32, full_height as i32) == 0 {
+                opj::opj_image_destroy(image);
+                opj::opj_destroy_codec(codec);
+                opj::opj_stream_destroy(stream);
+                return Err(Error::DecompressionError(
+                    "JP2K: opj_set_decode_area failed".to_string(),
+                ));
+            }
+
+            if opj::opj_decode(codec, stream, image) == 0 {
+                opj::opj_image_destroy(image);
+                opj::opj_destroy_codec(codec);
+                opj::opj_stream_destroy(stream);
+                return Err(Error::DecompressionError("JP2K: opj_decode failed".to_string()));
+            }
+            opj::opj_end_decompress(codec, stream);
+
+            let img = &*image;
+            let ncomps = img.numcomps as usize;
+            if ncomps != self.nbands {
+                opj::opj_image_destroy(image);
+                opj::opj_destroy_codec(codec);
+                opj::opj_stream_destroy(stream);
+                return Err(Error::DecompressionError(format!(
+                    "JP2K: codestream has {} components, IFD advertises {} bands",
+                    ncomps, self.nbands
+                )));
+            }
+
+            let components = std::slice::from_raw_parts(img.comps, ncomps);
+            let comp_width = components[0].w as usize;
+            let comp_height = components[0].h as usize;
+            if comp_width != width || comp_height != height {
+                opj::opj_image_destroy(image);
+                opj::opj_destroy_codec(codec);
+                opj::opj_stream_destroy(stream);
+                return Err(Error::DecompressionError(format!(
+                    "JP2K: decoded tile is {}x{}, expected {}x{}",
+                    comp_width, comp_height, width, height
+                )));
+            }
+            for (i, comp) in components.iter().enumerate() {
+                if comp.prec as usize != self.bit_depth {
+                    opj::opj_image_destroy(image);
+                    opj::opj_destroy_codec(codec);
+                    opj::opj_stream_destroy(stream);
+                    return Err(Error::DecompressionError(format!(
+                        "JP2K: component {} has {}-bit precision, IFD advertises {} bits",
+                        i, comp.prec, self.bit_depth
+                    )));
+                }
+            }
+
+            // Interleave the decoded component planes (OpenJPEG stores each component as its own
+            // contiguous `OPJ_INT32` plane) into this crate's row-major, byte-packed layout.
+            let npixels = comp_width * comp_height;
+            let sample_bytes = self.bit_depth.div_ceil(8);
+            let mut out = vec![0u8; npixels * ncomps * sample_bytes];
+            for (band, comp) in components.iter().enumerate() {
+                let plane = std::slice::from_raw_parts(comp.data, npixels);
+                for (pixel, value) in plane.iter().enumerate() {
+                    let offset = (pixel * ncomps + band) * sample_bytes;
+                    let bytes = (*value as u32).to_le_bytes();
+                    out[offset..offset + sample_bytes].copy_from_slice(&bytes[..sample_bytes]);
+                }
+            }
+
+            opj::opj_image_destroy(image);
+            opj::opj_destroy_codec(codec);
+            opj::opj_stream_destroy(stream);
+
+            Ok(out)
+        }
+    }
+}
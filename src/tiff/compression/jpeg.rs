@@ -11,11 +11,14 @@
 //
 // GDAL uses turbojpeg and it's possible to decode first the tables and then images using the C
 // API, but it's quite convoluted. turbojpeg v3 also supports tj3DecompressHeader which can take
-// just the JPEG tables. So using turbojpeg v3 through the CFFI would be an option
+// just the JPEG tables. So using turbojpeg v3 through the CFFI would be an option - see
+// `turbojpeg_backend` below, enabled by the `turbojpeg` feature.
 //
-// zune-jpeg doesn't seem to support that yet - it needs a whole jpeg image in one go. So we
-// rebuild that full image by concatenating the jpeg tables and the data stream. Doing so is not
-// optimal performance-wise though if we would read many tiles from the same COG at once
+// zune-jpeg doesn't seem to support that yet - it needs a whole jpeg image in one go. So by
+// default we rebuild that full image by splicing the jpeg tables and the tile's data stream back
+// together. Doing so is not optimal performance-wise though if we would read many tiles from the
+// same COG at once, since zune-jpeg re-parses the same Huffman/quantization tables on every tile -
+// hence the `turbojpeg` feature as a faster opt-in path for that case.
 //
 // ==== References
 // A GDAL COG seem to define JPEG tags:
@@ -31,61 +34,348 @@ use crate::Error;
 use zune_core::bytestream::ZCursor;
 use zune_jpeg::JpegDecoder;
 
+// https://www.disktuna.com/list-of-jpeg-markers/
+const SOI: u8 = 0xd8;
+const EOI: u8 = 0xd9;
+const SOF0: u8 = 0xc0;
+const SOF2: u8 = 0xc2;
+const DHT: u8 = 0xc4;
+const SOS: u8 = 0xda;
+const DQT: u8 = 0xdb;
+const APP0: u8 = 0xe0;
+const APP14: u8 = 0xee;
+
+// Markers with no length-prefixed payload: SOI/EOI and the 8 restart markers RST0-RST7.
+fn marker_has_length(marker: u8) -> bool {
+    !(marker == SOI || marker == EOI || (0xd0..=0xd7).contains(&marker))
+}
+
+// One marker segment found while walking a JPEG byte stream: its marker byte and the byte range
+// of the whole segment (marker + length + payload), relative to the stream passed to
+// `walk_segments`.
+struct Segment {
+    marker: u8,
+    range: std::ops::Range<usize>,
+}
+
+// Walks the marker segments of a JPEG byte stream that starts right after a leading SOI, up to and
+// including SOS (scan data itself has no marker structure, so walking stops there) or the end of
+// input. Returns an error if a marker's length field would run off the end of `data`.
+fn walk_segments(data: &[u8]) -> Result<Vec<Segment>, Error> {
+    let mut segments = Vec::new();
+    let mut pos = 0;
+    while pos + 1 < data.len() {
+        if data[pos] != 0xff {
+            return Err(Error::DecompressionError(format!(
+                "JPEG: expected a marker (0xff _) at offset {}, found {:#x}",
+                pos, data[pos]
+            )));
+        }
+        let marker = data[pos + 1];
+        let start = pos;
+        pos += 2;
+        if marker_has_length(marker) {
+            if pos + 2 > data.len() {
+                return Err(Error::DecompressionError(format!(
+                    "JPEG: marker {:#x} at offset {} is missing its length field",
+                    marker, start
+                )));
+            }
+            let length = u16::from_be_bytes([data[pos], data[pos + 1]]) as usize;
+            pos += length;
+            if pos > data.len() {
+                return Err(Error::DecompressionError(format!(
+                    "JPEG: marker {:#x} at offset {} has a length that overruns the stream",
+                    marker, start
+                )));
+            }
+        }
+        segments.push(Segment {
+            marker,
+            range: start..pos,
+        });
+        if marker == SOS {
+            break;
+        }
+    }
+    Ok(segments)
+}
+
 #[derive(Clone)]
 pub struct Decompressor {
-    jpeg_tables: Vec<u8>,
+    backend: Backend,
 }
 
-// https://www.disktuna.com/list-of-jpeg-markers/
-const START_OF_IMAGE: [u8; 2] = [0xff, 0xd8];
-const END_OF_IMAGE: [u8; 2] = [0xff, 0xd9];
+#[derive(Clone)]
+enum Backend {
+    // SOI, followed by the DQT/DHT/APP0/APP14 segments retained from the `JpegTables` tag (its own
+    // trailing EOI dropped). Concatenating this with a tile's body (after the tile's own SOI)
+    // reconstructs a single complete JPEG stream without re-parsing the tables on every tile.
+    Zune { tables_prefix: Vec<u8> },
+    #[cfg(feature = "turbojpeg")]
+    TurboJpeg(turbojpeg_backend::Decompressor),
+}
 
 impl Decompressor {
+    /// A placeholder `Decompressor`, good only for selecting `Compression::Jpeg(..)` as the codec
+    /// in `WriteOptions` - the writer never calls `decompress` on it (see `tiff::writer::write_level`,
+    /// which builds its own `jpeg::Encoder` and `JpegTables` tag instead of going through
+    /// `Compression::compress`). Calling `decompress` on this placeholder will fail, since it has no
+    /// real tables to splice tiles against.
+    pub fn for_writing() -> Decompressor {
+        Decompressor {
+            backend: Backend::Zune {
+                tables_prefix: vec![0xff, SOI],
+            },
+        }
+    }
+
     pub fn new(jpeg_tables: &[u8]) -> Result<Decompressor, Error> {
+        if jpeg_tables.len() < 2 || jpeg_tables[0] != 0xff || jpeg_tables[1] != SOI {
+            return Err(Error::DecompressionError(
+                "JPEG: JpegTables does not start with SOI".to_string(),
+            ));
+        }
+        let body = &jpeg_tables[2..];
+        let segments = walk_segments(body)?;
+        let mut tables_prefix = vec![0xff, SOI];
+        for seg in &segments {
+            if matches!(seg.marker, DQT | DHT | APP0 | APP14) {
+                tables_prefix.extend_from_slice(&body[seg.range.clone()]);
+            }
+        }
         Ok(Decompressor {
-            jpeg_tables: jpeg_tables.into(),
+            backend: Backend::Zune { tables_prefix },
         })
     }
 
-    pub fn decompress(
-        &self,
-        data: Vec<u8>,
-        _width: usize,
-        _height: usize,
-    ) -> Result<Vec<u8>, Error> {
-        // Since zune-jpeg doesn't support decoding first the huffman tables only and then the images
-        // from two stream, we concatenate both in one stream. This is suboptimal performance-wise,
-        // but it works.
-        //
-        // Both the jpeg_tables ("abbreviated table specification") and the data ("abbreviated image")
-        // will start with the START_OF_IMAGE (SOI) jpeg marker and end with the END_OF_IMAGE (EOI) one.
-        if self.jpeg_tables[self.jpeg_tables.len() - 2] != END_OF_IMAGE[0]
-            || self.jpeg_tables[self.jpeg_tables.len() - 1] != END_OF_IMAGE[1]
-        {
+    /// Like `new`, but decodes through turbojpeg instead of zune-jpeg: `jpeg_tables` is loaded once
+    /// via `tj3DecompressHeader` onto a long-lived handle, and each tile body is decompressed
+    /// directly against that same handle instead of being spliced back together with the tables on
+    /// every call. Only available with the `turbojpeg` feature, which statically links
+    /// libjpeg-turbo.
+    #[cfg(feature = "turbojpeg")]
+    pub fn new_turbojpeg(jpeg_tables: &[u8]) -> Result<Decompressor, Error> {
+        Ok(Decompressor {
+            backend: Backend::TurboJpeg(turbojpeg_backend::Decompressor::new(jpeg_tables)?),
+        })
+    }
+
+    pub fn decompress(&self, data: Vec<u8>, _width: usize, _height: usize) -> Result<Vec<u8>, Error> {
+        if data.len() < 2 || data[0] != 0xff || data[1] != SOI {
             return Err(Error::DecompressionError(format!(
-                "Expected END_OF_IMAGE marker ({:x?}, found {:x?}",
-                END_OF_IMAGE,
-                &self.jpeg_tables[self.jpeg_tables.len() - 2..],
+                "Expected a tile starting with SOI (0xff, 0xd8), found {:x?}",
+                &data[..data.len().min(2)],
             )));
         }
+        let body = &data[2..];
+        let segments = walk_segments(body)?;
+        if !segments.iter().any(|s| matches!(s.marker, SOF0 | SOF2)) {
+            return Err(Error::DecompressionError(
+                "JPEG: tile is missing its SOF marker".to_string(),
+            ));
+        }
+        if !segments.iter().any(|s| s.marker == SOS) {
+            return Err(Error::DecompressionError(
+                "JPEG: tile is missing its SOS marker".to_string(),
+            ));
+        }
 
-        if data[0] != START_OF_IMAGE[0] || data[1] != START_OF_IMAGE[1] {
-            return Err(Error::DecompressionError(format!(
-                "Expected START_OF_IMAGE marker ({:x?}, found {:x?}",
-                START_OF_IMAGE,
-                &data[0..2],
-            )));
+        match &self.backend {
+            Backend::Zune { tables_prefix } => {
+                // Concatenate the cached table segments with the tile's own body (after its SOI) so
+                // zune-jpeg sees a single complete stream.
+                let jpeg_image: Vec<u8> = [tables_prefix.as_slice(), body].concat();
+                let mut decoder = JpegDecoder::new(ZCursor::new(jpeg_image));
+                let pixels = decoder.decode()?;
+                Ok(pixels)
+            }
+            #[cfg(feature = "turbojpeg")]
+            Backend::TurboJpeg(decompressor) => decompressor.decompress(&data, _width, _height),
         }
+    }
+}
 
-        // Concatenate both, removing last 2 bytes (EOI) of the tables stream and first 2 bytes
-        // (SOI) of the data stream so that it looks like a single stream
-        let jpeg_image: Vec<u8> = [
-            &self.jpeg_tables.as_slice()[..self.jpeg_tables.len() - 2],
-            &data[2..],
-        ]
-        .concat();
-        let mut decoder = JpegDecoder::new(ZCursor::new(jpeg_image));
-        let pixels = decoder.decode()?;
-        Ok(pixels)
+// ==== Writer side ====
+//
+// `tiff::writer::write_level` needs two things per level that the generic `Compression::compress`
+// (a single tile in, single tile out) can't give it: a `JpegTables` tag value shared by every tile,
+// and each tile stored as an abbreviated image with those same tables stripped back out. `Encoder`
+// produces both by going through a normal, standalone `jpeg_encoder` crate encode and then running
+// the result back through `walk_segments` - the inverse of what `Decompressor::decompress` does to
+// reassemble one.
+
+/// One tile, split into the marker segments every tile in a level shares (`tables`, suitable as the
+/// level's `JpegTables` tag - only needed from the first tile encoded) and the segments unique to
+/// this tile (`abbreviated_image`, its on-disk bytes).
+pub struct EncodedTile {
+    pub tables: Vec<u8>,
+    pub abbreviated_image: Vec<u8>,
+}
+
+/// Encodes tiles as abbreviated JPEG images sharing one `JpegTables` blob, via the `jpeg_encoder`
+/// crate (a pure-Rust baseline JPEG encoder - no need for turbojpeg/libjpeg's extra complexity just
+/// to produce a standard stream for writing).
+pub struct Encoder {
+    quality: u8,
+}
+
+impl Encoder {
+    /// `quality` is passed straight through to `jpeg_encoder`, 1-100 as usual for JPEG.
+    pub fn new(quality: u8) -> Encoder {
+        Encoder { quality }
+    }
+
+    /// Encodes one tile's interleaved, row-major sample bytes (1 or 3 bands, 8-bit) into its
+    /// `tables`/`abbreviated_image` split.
+    pub fn encode(
+        &self,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        nbands: usize,
+    ) -> Result<EncodedTile, Error> {
+        let color_type = match nbands {
+            1 => jpeg_encoder::ColorType::Luma,
+            3 => jpeg_encoder::ColorType::Rgb,
+            n => {
+                return Err(Error::OtherError(format!(
+                    "JPEG writing only supports 1 or 3 bands, got {}",
+                    n
+                )))
+            }
+        };
+        let mut full = Vec::new();
+        {
+            let mut encoder = jpeg_encoder::Encoder::new(&mut full, self.quality);
+            encoder
+                .encode(data, width as u16, height as u16, color_type)
+                .map_err(|e| Error::OtherError(format!("JPEG compression error: {}", e)))?;
+        }
+        split_tables_and_body(&full)
+    }
+}
+
+// Splits a standalone JPEG stream (SOI..EOI) produced by `Encoder::encode` into its `DQT`/`DHT`/
+// `APP0`/`APP14` "tables" segments and everything else - the inverse of the concatenation
+// `Decompressor::decompress`'s `Zune` backend performs to reassemble one.
+fn split_tables_and_body(full: &[u8]) -> Result<EncodedTile, Error> {
+    if full.len() < 2 || full[0] != 0xff || full[1] != SOI {
+        return Err(Error::OtherError(
+            "JPEG: encoder did not produce a stream starting with SOI".to_string(),
+        ));
+    }
+    let body = &full[2..];
+    let segments = walk_segments(body)?;
+    if segments.last().map(|s| s.marker) != Some(SOS) {
+        return Err(Error::OtherError(
+            "JPEG: encoder output is missing its SOS marker".to_string(),
+        ));
+    }
+
+    let mut tables = vec![0xff, SOI];
+    let mut abbreviated_image = vec![0xff, SOI];
+    for seg in &segments {
+        if matches!(seg.marker, DQT | DHT | APP0 | APP14) {
+            tables.extend_from_slice(&body[seg.range.clone()]);
+        } else {
+            abbreviated_image.extend_from_slice(&body[seg.range.clone()]);
+        }
+    }
+    // `walk_segments` stops right after SOS's own header, since scan data that follows has no
+    // marker structure to walk - append the rest of the stream (the entropy-coded scan plus EOI)
+    // as-is.
+    let scan_start = segments.last().unwrap().range.end;
+    abbreviated_image.extend_from_slice(&body[scan_start..]);
+
+    Ok(EncodedTile {
+        tables,
+        abbreviated_image,
+    })
+}
+
+#[cfg(feature = "turbojpeg")]
+mod turbojpeg_backend {
+    use crate::Error;
+    use std::os::raw::c_int;
+    use std::sync::Mutex;
+    use turbojpeg_sys as tj;
+
+    // A `tjhandle` is an opaque pointer into libjpeg-turbo's internal `jpeg_decompress_struct`,
+    // which is exactly what needs to persist across calls for the abbreviated-tables-then-images
+    // trick below to work - so unlike `jpeg::Backend::Zune`, this can't just be re-derived from
+    // bytes on every `Decompressor::clone()`. It's wrapped in a `Mutex` (turbojpeg doesn't document
+    // its handles as safe to call concurrently) inside an `Arc` so cloning a `Decompressor` shares
+    // the one real handle instead of re-priming a fresh one.
+    struct Handle(tj::tjhandle);
+
+    unsafe impl Send for Handle {}
+
+    impl Drop for Handle {
+        fn drop(&mut self) {
+            unsafe {
+                tj::tj3Destroy(self.0);
+            }
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct Decompressor {
+        handle: std::sync::Arc<Mutex<Handle>>,
+    }
+
+    impl Decompressor {
+        pub fn new(jpeg_tables: &[u8]) -> Result<Decompressor, Error> {
+            let raw = unsafe { tj::tj3Init(tj::TJINIT_TJINIT_DECOMPRESS as c_int) };
+            if raw.is_null() {
+                return Err(Error::DecompressionError(
+                    "turbojpeg: tj3Init failed".to_string(),
+                ));
+            }
+            let handle = Handle(raw);
+            // Priming the handle with the abbreviated table specification: per the "abbreviated
+            // datastreams" mechanism libjpeg-turpo inherits from libjpeg, the tables a
+            // `tj3DecompressHeader` call loads onto a handle are retained across subsequent calls
+            // on that same handle, so a later header-less tile body can reuse them without
+            // re-parsing.
+            let rc = unsafe {
+                tj::tj3DecompressHeader(handle.0, jpeg_tables.as_ptr(), jpeg_tables.len())
+            };
+            if rc != 0 {
+                return Err(Error::DecompressionError(
+                    "turbojpeg: tj3DecompressHeader failed on JpegTables".to_string(),
+                ));
+            }
+            Ok(Decompressor {
+                handle: std::sync::Arc::new(Mutex::new(handle)),
+            })
+        }
+
+        pub fn decompress(&self, data: &[u8], width: usize, height: usize) -> Result<Vec<u8>, Error> {
+            let handle = self.handle.lock().unwrap();
+            let rc = unsafe { tj::tj3DecompressHeader(handle.0, data.as_ptr(), data.len()) };
+            if rc != 0 {
+                return Err(Error::DecompressionError(
+                    "turbojpeg: tj3DecompressHeader failed on tile body".to_string(),
+                ));
+            }
+            let mut out = vec![0u8; width * height * 3];
+            let rc = unsafe {
+                tj::tj3Decompress8(
+                    handle.0,
+                    data.as_ptr(),
+                    data.len(),
+                    out.as_mut_ptr(),
+                    0,
+                    tj::TJPF_TJPF_RGB as c_int,
+                )
+            };
+            if rc != 0 {
+                return Err(Error::DecompressionError(
+                    "turbojpeg: tj3Decompress8 failed".to_string(),
+                ));
+            }
+            Ok(out)
+        }
     }
 }
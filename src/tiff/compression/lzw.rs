@@ -0,0 +1,107 @@
+use super::Error;
+
+// TIFF's flavor of LZW: variable-width (9-12 bit), MSB-first packed codes. The dictionary starts
+// with the 256 literal byte values plus a ClearCode (256) and an EndOfInformation code (257); new
+// entries are appended as (previous_entry + first_byte_of_current_entry) after each non-clear
+// code. This mirrors the decoder in libtiff/ffmpeg's `tiff.c`.
+const CLEAR_CODE: u16 = 256;
+const END_OF_INFORMATION_CODE: u16 = 257;
+const MIN_CODE_WIDTH: u8 = 9;
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    bit_pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader { data, bit_pos: 0 }
+    }
+
+    /// Reads `width` bits MSB-first starting at the current position, or `None` if there aren't
+    /// enough bits left.
+    fn read_code(&mut self, width: u8) -> Option<u16> {
+        if (self.bit_pos + width as usize).div_ceil(8) > self.data.len() {
+            return None;
+        }
+        let mut code: u16 = 0;
+        for _ in 0..width {
+            let byte = self.data[self.bit_pos / 8];
+            let bit = (byte >> (7 - (self.bit_pos % 8))) & 1;
+            code = (code << 1) | bit as u16;
+            self.bit_pos += 1;
+        }
+        Some(code)
+    }
+}
+
+fn reset_table(table: &mut Vec<Vec<u8>>) {
+    table.clear();
+    for i in 0..256u16 {
+        table.push(vec![i as u8]);
+    }
+    // 256 and 257 are the ClearCode/EndOfInformation codes, not dictionary entries, but keeping
+    // them as placeholders means table indices line up with code values.
+    table.push(Vec::new());
+    table.push(Vec::new());
+}
+
+pub fn decompress_lzw(data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let mut reader = BitReader::new(&data);
+    let mut out = Vec::new();
+    let mut table: Vec<Vec<u8>> = Vec::new();
+    let mut code_width = MIN_CODE_WIDTH;
+    let mut prev: Option<Vec<u8>> = None;
+    reset_table(&mut table);
+
+    while let Some(code) = reader.read_code(code_width) {
+        if code == CLEAR_CODE {
+            reset_table(&mut table);
+            code_width = MIN_CODE_WIDTH;
+            prev = None;
+            continue;
+        }
+        if code == END_OF_INFORMATION_CODE {
+            break;
+        }
+
+        let entry = if (code as usize) < table.len() {
+            table[code as usize].clone()
+        } else if code as usize == table.len() {
+            // KwKwK: the code refers to the entry that's about to be added, so it decodes to
+            // (previous_entry + first_byte_of_previous_entry)
+            let prev_entry = prev.as_ref().ok_or_else(|| {
+                Error::DecompressionError(
+                    "LZW: code references an entry not yet in the table".to_string(),
+                )
+            })?;
+            let mut entry = prev_entry.clone();
+            entry.push(prev_entry[0]);
+            entry
+        } else {
+            return Err(Error::DecompressionError(format!(
+                "LZW: invalid code {} (table has {} entries)",
+                code,
+                table.len()
+            )));
+        };
+
+        out.extend_from_slice(&entry);
+
+        if let Some(prev_entry) = &prev {
+            let mut new_entry = prev_entry.clone();
+            new_entry.push(entry[0]);
+            table.push(new_entry);
+        }
+        prev = Some(entry);
+
+        if table.len() == 511 && code_width == 9 {
+            code_width = 10;
+        } else if table.len() == 1023 && code_width == 10 {
+            code_width = 11;
+        } else if table.len() == 2047 && code_width == 11 {
+            code_width = 12;
+        }
+    }
+    Ok(out)
+}
@@ -4,13 +4,28 @@ use crate::errors::Error;
 use crate::sources::Source;
 
 mod deflate;
-mod jpeg;
+#[cfg(feature = "jp2k")]
+mod jp2k;
+// `pub(crate)`, not private: `tiff::writer::write_level` needs `jpeg::Encoder`/`jpeg::EncodedTile`
+// directly, since writing JPEG tiles can't go through the generic `Compression::compress` (see its
+// doc comment).
+pub(crate) mod jpeg;
+mod lzw;
+mod packbits;
+mod zstd;
 
+// LZW (`lzw` module) and PackBits (`packbits` module) decompressors, alongside `Deflate`, are all
+// wired up below - see `decompress_into` for the dispatch.
 #[derive(Clone)]
 pub enum Compression {
     Raw,
     Deflate,
+    Lzw,
     Jpeg(jpeg::Decompressor),
+    Zstd,
+    PackBits,
+    #[cfg(feature = "jp2k")]
+    Jp2k(jp2k::Decompressor),
 }
 
 impl std::fmt::Debug for Compression {
@@ -18,15 +33,16 @@ impl std::fmt::Debug for Compression {
         match self {
             Self::Raw => write!(f, "Raw"),
             Self::Deflate => write!(f, "Deflate"),
+            Self::Lzw => write!(f, "Lzw"),
             Self::Jpeg(_) => write!(f, "Jpeg"),
+            Self::Zstd => write!(f, "Zstd"),
+            Self::PackBits => write!(f, "PackBits"),
+            #[cfg(feature = "jp2k")]
+            Self::Jp2k(_) => write!(f, "Jp2k"),
         }
     }
 }
 
-pub fn decompress_raw(data: Vec<u8>) -> Result<Vec<u8>, Error> {
-    Ok(data)
-}
-
 async fn jpeg_from_ifd(
     source: &mut Source,
     ifd: &ImageFileDirectory,
@@ -44,7 +60,26 @@ async fn jpeg_from_ifd(
     jpeg::Decompressor::new(&jpeg_tables)
 }
 
+#[cfg(feature = "jp2k")]
+async fn jp2k_from_ifd(
+    source: &mut Source,
+    ifd: &ImageFileDirectory,
+) -> Result<jp2k::Decompressor, Error> {
+    let nbands = ifd.get_u64_tag_value(source, IFDTag::SamplesPerPixel).await? as usize;
+    let bits_per_sample = ifd
+        .get_vec_short_tag_value(source, IFDTag::BitsPerSample)
+        .await?;
+    let bit_depth = *bits_per_sample.first().ok_or_else(|| {
+        Error::InvalidData("BitsPerSample is empty".to_string())
+    })? as usize;
+    jp2k::Decompressor::new(nbands, bit_depth)
+}
+
 impl Compression {
+    /// Reads the `Compression` IFD tag (259) and resolves it to a decoder - every tile/strip
+    /// in the IFD is then decompressed through `decompress`/`decompress_into` before the predictor
+    /// and sample unpacking run (see `OverviewDataReader::read_image_part_into`). PackBits, LZW,
+    /// and Deflate (plus its pre-Adobe alias) are all covered below already.
     pub async fn from_ifd(
         source: &mut Source,
         ifd: &ImageFileDirectory,
@@ -56,9 +91,23 @@ impl Compression {
         // https://www.awaresystems.be/imaging/tiff/tifftags/compression.html
         match compression_type {
             1 => Ok(Compression::Raw),
-            // Using COMPRESS=DEFLATE with GDAL generates tag 8 which is actually "Adobe deflate"
-            8 => Ok(Compression::Deflate),
+            5 => Ok(Compression::Lzw),
+            32773 => Ok(Compression::PackBits),
+            // Using COMPRESS=DEFLATE with GDAL generates tag 8 which is actually "Adobe deflate".
+            // Tag 32946 is the older, pre-Adobe "Deflate" compression tag; both wrap the same
+            // zlib stream, so they decode identically.
+            8 | 32946 => Ok(Compression::Deflate),
             7 => Ok(Compression::Jpeg(jpeg_from_ifd(source, ifd).await?)),
+            // GDAL/rasterio prefer this when the libdeflate/zstd codec is available, for its
+            // better speed/ratio tradeoff over DEFLATE. 34925 is an older, pre-standardization
+            // private tag value some early GDAL builds wrote for the same codec.
+            50000 | 34925 => Ok(Compression::Zstd),
+            #[cfg(feature = "jp2k")]
+            34712 => Ok(Compression::Jp2k(jp2k_from_ifd(source, ifd).await?)),
+            #[cfg(not(feature = "jp2k"))]
+            34712 => Err(Error::UnsupportedCompression(
+                "JPEG 2000 (compression 34712) support requires the `jp2k` feature".to_string(),
+            )),
             _ => Err(Error::UnsupportedCompression(format!(
                 "Unsupported compression {}",
                 compression_type
@@ -66,13 +115,113 @@ impl Compression {
         }
     }
 
-    // TODO: Should we expose a weezl-like `into_vec` instead ? That would allow reducing allocations
-    // from the caller
-    pub fn decompress(&self, data: Vec<u8>, width: usize, height: usize) -> Result<Vec<u8>, Error> {
+    /// Decompress into a caller-owned buffer, which is cleared (keeping its capacity) rather than
+    /// reallocated on every call. Tile decoding in a COG reader runs this per tile across
+    /// potentially thousands of tiles, so hoisting one scratch buffer out of that loop cuts a
+    /// fresh heap allocation per tile down to an occasional grow.
+    ///
+    /// `expected_len` is the packed (predictor-not-yet-reversed) tile size in bytes - only
+    /// `PackBits` needs it, to know where its RLE stream ends rather than running until the input
+    /// is exhausted (which can include trailing padding).
+    ///
+    /// Lzw/Jpeg/PackBits don't support streaming into a caller's buffer yet, so they still
+    /// decompress into a fresh `Vec` internally and copy the result into `out`.
+    ///
+    /// This takes `data: &[u8]` rather than a `BufRead`: a COG tile's compressed bytes are always
+    /// fetched as one contiguous range up front (see `OverviewDataReader::read_image_part_into`'s
+    /// `TileScratch`-backed reads), so there's nothing upstream to stream from - a `BufRead` source
+    /// would just wrap that same in-memory slice.
+    pub fn decompress_into(
+        &self,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        expected_len: usize,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        match self {
+            Compression::Raw => {
+                out.clear();
+                out.extend_from_slice(data);
+                Ok(())
+            }
+            Compression::Deflate => deflate::decompress_deflate_into(data, out),
+            Compression::Lzw => {
+                let decompressed = lzw::decompress_lzw(data.to_vec())?;
+                out.clear();
+                out.extend_from_slice(&decompressed);
+                Ok(())
+            }
+            Compression::Jpeg(decompressor) => {
+                let decompressed = decompressor.decompress(data.to_vec(), width, height)?;
+                out.clear();
+                out.extend_from_slice(&decompressed);
+                Ok(())
+            }
+            Compression::Zstd => zstd::decompress_zstd_into(data, out),
+            Compression::PackBits => {
+                let decompressed = packbits::decompress_packbits(data, expected_len)?;
+                out.clear();
+                out.extend_from_slice(&decompressed);
+                Ok(())
+            }
+            #[cfg(feature = "jp2k")]
+            Compression::Jp2k(decompressor) => {
+                let decompressed = decompressor.decompress(data.to_vec(), width, height)?;
+                out.clear();
+                out.extend_from_slice(&decompressed);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn decompress(
+        &self,
+        data: Vec<u8>,
+        width: usize,
+        height: usize,
+        expected_len: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let mut out = Vec::new();
+        self.decompress_into(&data, width, height, expected_len, &mut out)?;
+        Ok(out)
+    }
+
+    /// Compresses a tile's worth of raw, already-predictor-free sample bytes. `Raw`/`Deflate`/
+    /// `PackBits` are supported for writing - `Lzw`/`Zstd` have no encoder yet. `Jpeg` needs its
+    /// own writer-side path instead (see `tiff::writer::write_level`): a JPEG tile can't be
+    /// compressed in isolation the way the other codecs are, since the `JpegTables` tag it's
+    /// written alongside has to be derived once and shared across every tile.
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Compression::Raw => Ok(data.to_vec()),
+            Compression::Deflate => deflate::compress_deflate(data),
+            Compression::PackBits => Ok(packbits::compress_packbits(data)),
+            #[cfg(feature = "jp2k")]
+            Compression::Jp2k(_) => Err(Error::UnsupportedCompression(format!(
+                "Writing {:?} compressed tiles is not supported",
+                self
+            ))),
+            Compression::Lzw | Compression::Jpeg(_) | Compression::Zstd => {
+                Err(Error::UnsupportedCompression(format!(
+                    "Writing {:?} compressed tiles is not supported",
+                    self
+                )))
+            }
+        }
+    }
+
+    /// The TIFF `Compression` tag value (259) for this codec - the inverse of `from_ifd`.
+    pub fn tag_value(&self) -> u16 {
         match self {
-            Compression::Raw => decompress_raw(data),
-            Compression::Deflate => deflate::decompress_deflate(data),
-            Compression::Jpeg(decompressor) => Ok(decompressor.decompress(data, width, height)?),
+            Compression::Raw => 1,
+            Compression::Lzw => 5,
+            Compression::Deflate => 8,
+            Compression::Jpeg(_) => 7,
+            Compression::Zstd => 50000,
+            Compression::PackBits => 32773,
+            #[cfg(feature = "jp2k")]
+            Compression::Jp2k(_) => 34712,
         }
     }
 }
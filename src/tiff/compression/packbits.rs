@@ -0,0 +1,78 @@
+use super::Error;
+
+/// TIFF's PackBits byte RLE (compression tag 32773): a header byte `n` followed by either `n + 1`
+/// literal bytes (`n` in `0..=127`) or a single byte repeated `257 - n` times (`n` in `129..=255`);
+/// `128` is a no-op padding byte some encoders emit at the end of a stream.
+/// https://www.awaresystems.be/imaging/tiff/tifftags/compression.html
+// Length of the run of identical bytes starting at `data[pos]`, capped at 128 (the longest run a
+// single PackBits repeat header can encode).
+fn run_length(data: &[u8], pos: usize) -> usize {
+    let byte = data[pos];
+    let mut len = 1;
+    while pos + len < data.len() && data[pos + len] == byte && len < 128 {
+        len += 1;
+    }
+    len
+}
+
+/// Encodes `data` as a PackBits byte stream `decompress_packbits` can reverse. Not bit-for-bit
+/// identical to any particular encoder's output (PackBits doesn't define a canonical encoding),
+/// but always at least as compact as storing `data` raw: a 2-byte repeat header is only emitted
+/// for runs of 2 or more, and literal runs only break early for a run of 3+ (the break-even point
+/// where a repeat header pays for itself).
+pub fn compress_packbits(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        let run_len = run_length(data, i);
+        if run_len >= 2 {
+            out.push((257 - run_len) as u8);
+            out.push(data[i]);
+            i += run_len;
+            continue;
+        }
+        let start = i;
+        i += 1;
+        while i < data.len() && i - start < 128 && run_length(data, i) < 3 {
+            i += 1;
+        }
+        let len = i - start;
+        out.push((len - 1) as u8);
+        out.extend_from_slice(&data[start..i]);
+    }
+    out
+}
+
+pub fn decompress_packbits(data: &[u8], expected_len: usize) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::with_capacity(expected_len);
+    let mut pos = 0;
+    while pos < data.len() && out.len() < expected_len {
+        let n = data[pos];
+        pos += 1;
+        match n {
+            0..=127 => {
+                let count = n as usize + 1;
+                let end = pos + count;
+                if end > data.len() {
+                    return Err(Error::DecompressionError(format!(
+                        "PackBits: literal run of {} bytes at offset {} overruns input",
+                        count, pos
+                    )));
+                }
+                out.extend_from_slice(&data[pos..end]);
+                pos = end;
+            }
+            129..=255 => {
+                let count = 257 - n as usize;
+                let byte = *data.get(pos).ok_or_else(|| {
+                    Error::DecompressionError("PackBits: repeat run missing its byte".to_string())
+                })?;
+                out.extend(std::iter::repeat_n(byte, count));
+                pos += 1;
+            }
+            // 128 is a no-op
+            128 => {}
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,15 @@
+use super::Error;
+use std::io::Read;
+
+/// Decompress into a caller-owned buffer, which is cleared (keeping its capacity) rather than
+/// reallocated - lets callers decoding many tiles hoist one scratch buffer out of their loop.
+pub fn decompress_zstd_into(data: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+    out.clear();
+    let mut decoder = zstd::stream::read::Decoder::new(data).map_err(|e| {
+        Error::DecompressionError(format!("zstd: failed to initialize decoder: {}", e))
+    })?;
+    decoder
+        .read_to_end(out)
+        .map_err(|e| Error::DecompressionError(format!("zstd decompression error: {}", e)))?;
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use super::ifd::{IFDTag, ImageFileDirectory};
+use super::low_level::Endian;
 use crate::errors::Error;
 use crate::image;
 use crate::sources::Source;
@@ -8,38 +9,96 @@ use crate::sources::Source;
 pub enum InternalDataType {
     // Mask are packed as 8 values into a byte on disk
     Mask,
+    // Intra-byte packed samples, e.g. 2 or 4-bit palette/grayscale imagery. Holds BitsPerSample.
+    Packed(u8),
     Uint8,
+    Int16,
+    Uint16,
+    Int32,
+    Uint32,
     Float32,
+    Float64,
 }
 
-fn unpack_bitmask(bytes: &[u8]) -> Vec<u8> {
-    let mut result = Vec::with_capacity(bytes.len() * 8);
-
-    for byte in bytes {
-        for bit_position in 0..8 {
-            let bit = (*byte >> bit_position) & 1;
-            result.push(bit * 255);
+// Unpacks `bits`-per-sample values out of `data`, one tile row at a time. Per the TIFF spec, each
+// row is individually padded to a byte boundary, so the bit cursor must reset at every
+// `tile_width * nbands` samples rather than running continuously across the whole tile; samples
+// are packed MSB-first within each byte.
+fn unpack_packed_samples(data: &[u8], bits: usize, tile_width: usize, nbands: usize) -> Vec<u8> {
+    let samples_per_row = tile_width * nbands;
+    let row_bits = samples_per_row * bits;
+    let row_bytes = row_bits.div_ceil(8);
+    let max_value = (1u16 << bits) - 1;
+    let mut result = Vec::with_capacity((data.len() / row_bytes.max(1)) * samples_per_row);
+    for row in data.chunks(row_bytes) {
+        let mut bit_cursor = 0usize;
+        for _ in 0..samples_per_row {
+            let mut value: u16 = 0;
+            for _ in 0..bits {
+                let byte = row[bit_cursor / 8];
+                let bit = (byte >> (7 - bit_cursor % 8)) & 1;
+                value = (value << 1) | bit as u16;
+                bit_cursor += 1;
+            }
+            // A 1-bit Mask band represents "is this pixel opaque", so stretch it to a usable
+            // alpha value (0 or 255) rather than leaving it as 0/1.
+            let out = if bits == 1 {
+                value as u8 * 255
+            } else {
+                value as u8
+            };
+            debug_assert!(value <= max_value);
+            result.push(out);
         }
     }
-
     result
 }
 
 impl InternalDataType {
-    // Some datatype (well, Mask) is stored packed as 8 values per byte. So we need an
-    // "unpack" step first
-    pub fn unpack_bytes(&self, data: &[u8]) -> Vec<u8> {
+    // Mask and Packed data types are stored with multiple samples packed into a byte, so they need
+    // an "unpack" step first. `tile_width`/`nbands` are needed to find each row's start, since
+    // rows are padded to a byte boundary independently of one another.
+    pub fn unpack_bytes(&self, data: &[u8], tile_width: usize, nbands: usize) -> Vec<u8> {
         match self {
-            InternalDataType::Mask => unpack_bitmask(data),
-            InternalDataType::Uint8 | InternalDataType::Float32 => data.to_vec(),
+            InternalDataType::Mask => unpack_packed_samples(data, 1, tile_width, nbands),
+            InternalDataType::Packed(bits) => {
+                unpack_packed_samples(data, *bits as usize, tile_width, nbands)
+            }
+            InternalDataType::Uint8
+            | InternalDataType::Int16
+            | InternalDataType::Uint16
+            | InternalDataType::Int32
+            | InternalDataType::Uint32
+            | InternalDataType::Float32
+            | InternalDataType::Float64 => data.to_vec(),
         }
     }
 
     // The corresponding data type after the data has gone through `unpack_bytes`
     pub fn unpacked_type(&self) -> image::DataType {
         match self {
-            InternalDataType::Mask | InternalDataType::Uint8 => image::DataType::Uint8,
+            InternalDataType::Mask | InternalDataType::Packed(_) | InternalDataType::Uint8 => {
+                image::DataType::Uint8
+            }
+            InternalDataType::Int16 => image::DataType::Int16,
+            InternalDataType::Uint16 => image::DataType::Uint16,
+            InternalDataType::Int32 => image::DataType::Int32,
+            InternalDataType::Uint32 => image::DataType::Uint32,
             InternalDataType::Float32 => image::DataType::Float32,
+            InternalDataType::Float64 => image::DataType::Float64,
+        }
+    }
+
+    // The BitsPerSample this data type is stored as on disk, before `unpack_bytes`. Needed by
+    // `Predictor::reconstruct`, which operates on the still-packed, still-native-width samples.
+    pub fn bit_depth(&self) -> usize {
+        match self {
+            InternalDataType::Mask => 1,
+            InternalDataType::Packed(bits) => *bits as usize,
+            InternalDataType::Uint8 => 8,
+            InternalDataType::Int16 | InternalDataType::Uint16 => 16,
+            InternalDataType::Int32 | InternalDataType::Uint32 | InternalDataType::Float32 => 32,
+            InternalDataType::Float64 => 64,
         }
     }
 }
@@ -64,6 +123,27 @@ fn check_all_same(numbers: &[u16]) -> Result<u16, Error> {
     Ok(first_value)
 }
 
+// Pixel/tile decoding (`image::DataType::read_sample`, `predictor::reconstruct_*`) assumes
+// little-endian multi-byte samples - fine for 8-bit-or-less samples (and packed/mask data, which
+// has no byte order to begin with), but a genuinely big-endian 16/32/64-bit sample would silently
+// decode byte-swapped. Reject that case explicitly rather than producing corrupted pixel values,
+// until that decode path also byte-swaps on `Endian::BigEndian`.
+fn reject_unsupported_big_endian_samples(
+    endian: Endian,
+    bits_per_sample: u16,
+) -> Result<(), Error> {
+    if endian == Endian::BigEndian && bits_per_sample > 8 {
+        return Err(Error::UnsupportedTagValue(
+            IFDTag::BitsPerSample,
+            format!(
+                "Big-endian TIFFs with BitsPerSample={} (>8) aren't supported yet",
+                bits_per_sample
+            ),
+        ));
+    }
+    Ok(())
+}
+
 pub async fn data_type_from_ifd(
     ifd: &ImageFileDirectory,
     source: &mut Source,
@@ -76,11 +156,20 @@ pub async fn data_type_from_ifd(
         &ifd.get_vec_short_tag_value(source, IFDTag::BitsPerSample)
             .await?,
     )?;
+    reject_unsupported_big_endian_samples(ifd.endian, bits_per_sample)?;
     if sample_format == 1 {
+        // unsigned integer
         if bits_per_sample == 1 {
             Ok(InternalDataType::Mask)
+        } else if bits_per_sample == 2 || bits_per_sample == 4 {
+            // Sub-byte packed samples: typically palette-indexed or packed grayscale imagery
+            Ok(InternalDataType::Packed(bits_per_sample as u8))
         } else if bits_per_sample == 8 {
             Ok(InternalDataType::Uint8)
+        } else if bits_per_sample == 16 {
+            Ok(InternalDataType::Uint16)
+        } else if bits_per_sample == 32 {
+            Ok(InternalDataType::Uint32)
         } else {
             Err(Error::UnsupportedDataType(format!(
                 "SampleFormat={}, BitsPerSample={}",
@@ -88,15 +177,23 @@ pub async fn data_type_from_ifd(
             )))
         }
     } else if sample_format == 2 {
-        // int
-        Err(Error::UnsupportedDataType(format!(
-            "SampleFormat={}",
-            sample_format
-        )))
+        // signed integer
+        if bits_per_sample == 16 {
+            Ok(InternalDataType::Int16)
+        } else if bits_per_sample == 32 {
+            Ok(InternalDataType::Int32)
+        } else {
+            Err(Error::UnsupportedDataType(format!(
+                "SampleFormat={}, BitsPerSample={}",
+                sample_format, bits_per_sample
+            )))
+        }
     } else if sample_format == 3 {
         // float
         if bits_per_sample == 32 {
             Ok(InternalDataType::Float32)
+        } else if bits_per_sample == 64 {
+            Ok(InternalDataType::Float64)
         } else {
             Err(Error::UnsupportedDataType(format!(
                 "SampleFormat={}, BitsPerSample={}",
@@ -110,3 +207,19 @@ pub async fn data_type_from_ifd(
         )))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{reject_unsupported_big_endian_samples, Endian};
+
+    #[test]
+    fn test_reject_unsupported_big_endian_samples() {
+        assert!(reject_unsupported_big_endian_samples(Endian::BigEndian, 16).is_err());
+        assert!(reject_unsupported_big_endian_samples(Endian::BigEndian, 32).is_err());
+        // 8-bit-or-less samples have no byte order to get wrong, even big-endian
+        assert!(reject_unsupported_big_endian_samples(Endian::BigEndian, 8).is_ok());
+        assert!(reject_unsupported_big_endian_samples(Endian::BigEndian, 1).is_ok());
+        // Little-endian is always fine regardless of bit depth
+        assert!(reject_unsupported_big_endian_samples(Endian::LittleEndian, 32).is_ok());
+    }
+}
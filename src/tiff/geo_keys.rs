@@ -10,12 +10,26 @@ pub enum KeyID {
     GTCitation,
     GeodeticCRS,
     GeogCitation,
+    GeogGeodeticDatum,
+    GeogPrimeMeridian,
     GeodeticLinearUnits,
     GeodeticAngularUnits,
     EllipsoidSemiMajorAxis,
     EllipsoidInvFlattening,
+    GeogEllipsoid,
     ProjectedCRS,
+    ProjCoordTrans,
     ProjLinearUnits,
+    ProjStdParallel1,
+    ProjStdParallel2,
+    ProjNatOriginLong,
+    ProjNatOriginLat,
+    ProjFalseEasting,
+    ProjFalseNorthing,
+    ProjFalseOriginLong,
+    ProjFalseOriginLat,
+    ProjScaleAtNatOrigin,
+    ProjAzimuthAngle,
     UnknownKey(u16),
 }
 
@@ -26,12 +40,26 @@ fn decode_key_id(v: u16) -> KeyID {
         1026 => KeyID::GTCitation,
         2048 => KeyID::GeodeticCRS,
         2049 => KeyID::GeogCitation,
+        2050 => KeyID::GeogGeodeticDatum,
+        2051 => KeyID::GeogPrimeMeridian,
         2052 => KeyID::GeodeticLinearUnits,
         2054 => KeyID::GeodeticAngularUnits,
+        2056 => KeyID::GeogEllipsoid,
         2057 => KeyID::EllipsoidSemiMajorAxis,
         2059 => KeyID::EllipsoidInvFlattening,
         3072 => KeyID::ProjectedCRS,
+        3075 => KeyID::ProjCoordTrans,
         3076 => KeyID::ProjLinearUnits,
+        3078 => KeyID::ProjStdParallel1,
+        3079 => KeyID::ProjStdParallel2,
+        3080 => KeyID::ProjNatOriginLong,
+        3081 => KeyID::ProjNatOriginLat,
+        3082 => KeyID::ProjFalseEasting,
+        3083 => KeyID::ProjFalseNorthing,
+        3084 => KeyID::ProjFalseOriginLong,
+        3085 => KeyID::ProjFalseOriginLat,
+        3092 => KeyID::ProjScaleAtNatOrigin,
+        3094 => KeyID::ProjAzimuthAngle,
         v => KeyID::UnknownKey(v),
     }
 }
@@ -150,6 +178,11 @@ impl GeoKeyEntry {
     }
 }
 
+/// The fully decoded `GeoKeyDirectoryTag` (34735), plus whichever `GeoDoubleParamsTag` (34736) /
+/// `GeoAsciiParamsTag` (34737) entries its keys pointed into - see `from_ifd`. `COG::open` reads
+/// this once per file (not per IFD - the spec only allows one GeoKey directory) and exposes it as
+/// `COG::geo_keys`; `georef::Georeference::decode` is what turns these raw keys into a CRS/
+/// geotransform a caller can actually use.
 #[derive(Debug)]
 pub struct GeoKeyDirectory {
     keys: Vec<GeoKeyEntry>,
@@ -175,6 +208,17 @@ impl GeoKeyDirectory {
         Ok(self.get_vec_short_key_value(id)?[0])
     }
 
+    pub fn get_vec_double_key_value(&self, id: KeyID) -> Result<&Vec<f64>, Error> {
+        match self.get_key_value(id)? {
+            KeyValue::Double(values) => Ok(values),
+            value => Err(Error::GeoKeyHasWrongType(id, value.clone())),
+        }
+    }
+
+    pub fn get_double_key_value(&self, id: KeyID) -> Result<f64, Error> {
+        Ok(self.get_vec_double_key_value(id)?[0])
+    }
+
     pub async fn from_ifd(
         ifd: &ImageFileDirectory,
         source: &mut CachedSource,
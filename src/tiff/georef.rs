@@ -1,9 +1,47 @@
 use super::geo_keys::{GeoKeyDirectory, KeyID};
 use super::ifd::{IFDTag, ImageFileDirectory};
-use crate::epsg::{spheroid_3857::EARTH_EQUATOR_CIRCUMFERENCE, Crs, UnitOfMeasure};
+use crate::epsg::{
+    spheroid_3857::EARTH_EQUATOR_CIRCUMFERENCE, Crs, UnitOfMeasure, UserDefinedProjectionParameters,
+};
+use crate::math::Vec2f;
 use crate::sources::Source;
 use crate::Error;
 
+// ProjectedCSTypeGeoKey/GeodeticCRS using this value means the CRS is user-defined rather than a
+// plain EPSG code - see the CRS itself is built from ProjCoordTransGeoKey and friends instead
+const USER_DEFINED: u16 = 32767;
+
+fn get_optional_double_geo_key(
+    geo_keys: &GeoKeyDirectory,
+    id: KeyID,
+) -> Result<Option<f64>, Error> {
+    match geo_keys.get_double_key_value(id) {
+        Ok(v) => Ok(Some(v)),
+        Err(Error::RequiredGeoKeyNotFound(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn decode_user_defined_crs(geo_keys: &GeoKeyDirectory) -> Result<Crs, Error> {
+    let coord_trans_method = geo_keys.get_short_key_value(KeyID::ProjCoordTrans)?;
+    let parameters = UserDefinedProjectionParameters {
+        std_parallel_1: get_optional_double_geo_key(geo_keys, KeyID::ProjStdParallel1)?,
+        std_parallel_2: get_optional_double_geo_key(geo_keys, KeyID::ProjStdParallel2)?,
+        nat_origin_long: get_optional_double_geo_key(geo_keys, KeyID::ProjNatOriginLong)?,
+        nat_origin_lat: get_optional_double_geo_key(geo_keys, KeyID::ProjNatOriginLat)?,
+        false_easting: get_optional_double_geo_key(geo_keys, KeyID::ProjFalseEasting)?,
+        false_northing: get_optional_double_geo_key(geo_keys, KeyID::ProjFalseNorthing)?,
+        false_origin_long: get_optional_double_geo_key(geo_keys, KeyID::ProjFalseOriginLong)?,
+        false_origin_lat: get_optional_double_geo_key(geo_keys, KeyID::ProjFalseOriginLat)?,
+        scale_at_nat_origin: get_optional_double_geo_key(geo_keys, KeyID::ProjScaleAtNatOrigin)?,
+        azimuth_angle: get_optional_double_geo_key(geo_keys, KeyID::ProjAzimuthAngle)?,
+    };
+    Ok(Crs::UserDefined {
+        coord_trans_method,
+        parameters,
+    })
+}
+
 pub fn lon_to_meters_equator(lon: f64) -> f64 {
     lon * EARTH_EQUATOR_CIRCUMFERENCE / 360.0
 }
@@ -13,8 +51,16 @@ pub fn meters_to_lon_equator(m: f64) -> f64 {
     m / lon_to_meters_equator(1.0)
 }
 
-/// A Geotransform, inspired by GDAL but enforcing north-up images
+/// A Geotransform, inspired by GDAL's affine geotransform
 /// https://gdal.org/tutorials/geotransforms_tut.html
+///
+/// This is the general 6-parameter affine mapping from (col, row) pixel space to CRS space:
+///   x = ul_x + col * x_res    + row * x_rotation
+///   y = ul_y + col * y_rotation + row * y_res
+///
+/// `x_rotation`/`y_rotation` are 0 for the common north-up case (tie-point + pixel-scale
+/// georeferencing), and non-zero when the raster was georeferenced with a full
+/// ModelTransformationTag (rotated/sheared imagery).
 #[derive(Debug, Clone)]
 pub struct Geotransform {
     // x coordinate of the upper left corner of the upper left pixel
@@ -24,6 +70,9 @@ pub struct Geotransform {
     // pixel dimensions
     pub x_res: f64,
     pub y_res: f64,
+    // row/column shear terms - 0 unless the raster is rotated/sheared
+    pub x_rotation: f64,
+    pub y_rotation: f64,
 }
 
 fn close(a: f64, b: f64) -> bool {
@@ -40,9 +89,9 @@ impl Geotransform {
         }
         // TODO: Here, need to look at raster_type to shift by 0.5 because the
         // geotransform ul_x is the coord of the uper left corner of upper left pixel
-        if !close(tie_points[0], 0.0) || !close(tie_points[1], 0.0) || !close(tie_points[2], 0.0) {
+        if !close(tie_points[2], 0.0) {
             return Err(Error::UnsupportedProjection(format!(
-                "Expected tie_points starting with [0, 0, 0]. Got {:?}",
+                "Expected tie_points raster Z (tie_points[2]) to be 0, got {:?}",
                 tie_points
             )));
         }
@@ -53,20 +102,93 @@ impl Geotransform {
             )));
         }
         // TODO: Do we need to check axis mapping like GDAL (i.e. some CRS have 0 has x, some 0 as y ?)
+        let (i, j) = (tie_points[0], tie_points[1]);
+        let (x, y) = (tie_points[3], tie_points[4]);
+        let (sx, sy) = (pixel_scale[0], pixel_scale[1]);
         Ok(Geotransform {
-            ul_x: tie_points[3],
-            ul_y: tie_points[4],
-            x_res: pixel_scale[0],
+            ul_x: x - i * sx,
+            ul_y: y + j * sy,
+            x_res: sx,
             // TODO: This should depend on the TIFF Orientation tag + CRS ?, but if not specified, it defaults to 1
             // which means that y grows downwards, which requires a - here because the geographic CRS have
             // y grow upwards (all of them ?)
-            y_res: -pixel_scale[1],
+            y_res: -sy,
+            x_rotation: 0.0,
+            y_rotation: 0.0,
+        })
+    }
+
+    /// Decode a Geotransform from a ModelTransformationTag (34264), i.e. the row-major 4x4
+    /// matrix described in "2.6.2 Transformation Matrix" of the GeoTIFF spec:
+    ///   | x |   | m0  m1  m2  m3  |   | col |
+    ///   | y | = | m4  m5  m6  m7  | * | row |
+    ///   | z |   | m8  m9  m10 m11 |   | 0   |
+    ///   | 1 |   | 0   0   0   1   |   | 1   |
+    /// We only support the 2D case used for rotated/sheared rasters, i.e. a trivial Z row/column.
+    ///
+    /// This can return a `Geotransform` with non-zero `x_rotation`/`y_rotation`, but
+    /// `Georeference::decode` - the only caller - rejects those before a rotated/sheared raster
+    /// can actually be opened (see the comment there): arbitrary rotated/sheared georeferencing
+    /// isn't supported end-to-end, by design, not merely unimplemented.
+    pub fn decode_from_matrix(matrix: &[f64]) -> Result<Geotransform, Error> {
+        if matrix.len() != 16 {
+            return Err(Error::UnsupportedProjection(format!(
+                "Expected a 4x4 ModelTransformationTag matrix (16 values), got {}",
+                matrix.len()
+            )));
+        }
+        if !close(matrix[2], 0.0)
+            || !close(matrix[6], 0.0)
+            || !close(matrix[8], 0.0)
+            || !close(matrix[9], 0.0)
+            || !close(matrix[10], 1.0)
+            || !close(matrix[11], 0.0)
+        {
+            return Err(Error::UnsupportedProjection(format!(
+                "Only 2D (rotated/sheared) ModelTransformationTag matrices are supported, got {:?}",
+                matrix
+            )));
+        }
+        Ok(Geotransform {
+            ul_x: matrix[3],
+            ul_y: matrix[7],
+            x_res: matrix[0],
+            y_res: matrix[5],
+            x_rotation: matrix[1],
+            y_rotation: matrix[4],
         })
     }
 
     /// Return the average pixel resolution in the unit of the Georeference
     pub fn pixel_resolution(&self) -> f64 {
-        (self.x_res.abs() + self.y_res.abs()) / 2.0
+        (self.x_res * self.y_res - self.x_rotation * self.y_rotation)
+            .abs()
+            .sqrt()
+    }
+
+    /// Convert a `(col, row)` pixel coordinate into CRS space using this affine geotransform.
+    pub fn pixel_to_world(&self, pixel: Vec2f) -> Vec2f {
+        Vec2f {
+            x: self.ul_x + pixel.x * self.x_res + pixel.y * self.x_rotation,
+            y: self.ul_y + pixel.x * self.y_rotation + pixel.y * self.y_res,
+        }
+    }
+
+    /// Invert [`Geotransform::pixel_to_world`] - convert a CRS space coordinate back into
+    /// `(col, row)` pixel space.
+    pub fn world_to_pixel(&self, world: Vec2f) -> Result<Vec2f, Error> {
+        let det = self.x_res * self.y_res - self.x_rotation * self.y_rotation;
+        if close(det, 0.0) {
+            return Err(Error::UnsupportedProjection(
+                "Geotransform is singular (zero determinant), cannot invert".to_string(),
+            ));
+        }
+        let dx = world.x - self.ul_x;
+        let dy = world.y - self.ul_y;
+        Ok(Vec2f {
+            x: (dx * self.y_res - dy * self.x_rotation) / det,
+            y: (dy * self.x_res - dx * self.y_rotation) / det,
+        })
     }
 }
 
@@ -78,6 +200,45 @@ pub struct Georeference {
 }
 
 impl Georeference {
+    /// Decode the affine Geotransform from either a ModelTransformationTag, or a single
+    /// ModelTiepointTag + ModelPixelScaleTag.
+    ///
+    /// Rasters carrying more than one tie point (multiple GCPs, with no single affine transform
+    /// between them) are rejected below, by design, not because fitting one is merely
+    /// unimplemented: callers would need to supply their own polynomial/TPS fit, and nothing
+    /// downstream of `Georeference` consumes that today.
+    async fn decode_geo_transform(
+        ifd: &ImageFileDirectory,
+        source: &mut Source,
+    ) -> Result<Geotransform, Error> {
+        match ifd
+            .get_vec_double_tag_value(source, IFDTag::ModelTransformationTag)
+            .await
+        {
+            Ok(matrix) => return Geotransform::decode_from_matrix(&matrix),
+            Err(Error::RequiredTagNotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+        let tie_points = ifd
+            .get_vec_double_tag_value(source, IFDTag::ModelTiepointTag)
+            .await?;
+        if tie_points.len() == 6 {
+            let pixel_scale = ifd
+                .get_vec_double_tag_value(source, IFDTag::ModelPixelScaleTag)
+                .await?;
+            return Geotransform::decode(&tie_points, &pixel_scale);
+        }
+        // GCP math (fitting a pixel<->world transform from more than one tie point) isn't
+        // implemented anywhere downstream yet, so - same as `Geotransform::decode` does for a
+        // malformed tie_points length above - fail cleanly here rather than letting `COG::open`
+        // succeed on a raster every georeferencing call would then panic on.
+        Err(Error::UnsupportedProjection(format!(
+            "Currently only support rasters georeferenced with an affine geotransform. Got {} \
+             tie points instead of a single one - GCP-only rasters aren't supported yet.",
+            tie_points.len() / 6
+        )))
+    }
+
     pub async fn decode(
         ifd: &ImageFileDirectory,
         source: &mut Source,
@@ -86,7 +247,12 @@ impl Georeference {
         let (crs, unit) = {
             let model_type = geo_keys.get_short_key_value(KeyID::GTModelType)?;
             if model_type == 1 {
-                let crs = Crs::decode(geo_keys.get_short_key_value(KeyID::ProjectedCRS)?);
+                let projected_crs = geo_keys.get_short_key_value(KeyID::ProjectedCRS)?;
+                let crs = if projected_crs == USER_DEFINED {
+                    decode_user_defined_crs(geo_keys)?
+                } else {
+                    Crs::decode(projected_crs)
+                };
                 let unit =
                     UnitOfMeasure::decode(geo_keys.get_short_key_value(KeyID::ProjLinearUnits)?)?;
                 (crs, unit)
@@ -113,13 +279,20 @@ impl Georeference {
 
         // We are assuming that the geotransform is affine - which isn't necessarily the case.
         // See "B.6 GeoTIFF Tags for Coordinate Transformations" of the spec for more details
-        let tie_points = ifd
-            .get_vec_double_tag_value(source, IFDTag::ModelTiepointTag)
-            .await?;
-        let pixel_scale = ifd
-            .get_vec_double_tag_value(source, IFDTag::ModelPixelScaleTag)
-            .await?;
-        let geo_transform = Geotransform::decode(&tie_points, &pixel_scale)?;
+        let geo_transform = Self::decode_geo_transform(ifd, source).await?;
+        // `Geotransform::decode_from_matrix` accepts rotated/sheared ModelTransformationTag
+        // matrices, but every read/tile-serving path downstream (`Warper::project_grid_point`,
+        // `COG::georeference_x_y_res`, `COG::read_region_at_resolution`, `lnglat_bounds`, ...)
+        // assumes an axis-aligned pixel grid and would silently produce wrong pixel coordinates
+        // for a non-zero rotation/shear. Reject those here, the same way GCP-only rasters are
+        // rejected above, instead of shipping a "supported" feature that quietly corrupts output.
+        if !close(geo_transform.x_rotation, 0.0) || !close(geo_transform.y_rotation, 0.0) {
+            return Err(Error::UnsupportedProjection(format!(
+                "Rotated/sheared rasters (ModelTransformationTag with non-zero rotation terms) \
+                 aren't supported yet, got x_rotation={}, y_rotation={}",
+                geo_transform.x_rotation, geo_transform.y_rotation
+            )));
+        }
         Ok(Georeference {
             crs,
             unit,
@@ -128,13 +301,25 @@ impl Georeference {
     }
 
     pub fn pixel_resolution_in_meters(&self) -> f64 {
+        let pixel_resolution = self.geo_transform.pixel_resolution();
         match self.unit {
-            UnitOfMeasure::LinearMeter => self.geo_transform.pixel_resolution(),
+            UnitOfMeasure::LinearMeter => pixel_resolution,
             UnitOfMeasure::Degree => {
                 // TODO: Should we instead take a lon/lat as input to this function and compute
                 // actual distance using PROJ `proj_lp_dist` or similar ?
-                lon_to_meters_equator(self.geo_transform.pixel_resolution())
+                lon_to_meters_equator(pixel_resolution)
             }
         }
     }
+
+    /// Convert a `(col, row)` pixel coordinate into CRS space.
+    pub fn pixel_to_world(&self, pixel: Vec2f) -> Vec2f {
+        self.geo_transform.pixel_to_world(pixel)
+    }
+
+    /// Convert a CRS space coordinate back into `(col, row)` pixel space. See
+    /// [`Georeference::pixel_to_world`].
+    pub fn world_to_pixel(&self, world: Vec2f) -> Result<Vec2f, Error> {
+        self.geo_transform.world_to_pixel(world)
+    }
 }
@@ -91,6 +91,7 @@ pub enum IFDTag {
     // Geotiff tags
     ModelPixelScaleTag,
     ModelTiepointTag,
+    ModelTransformationTag,
     GeoKeyDirectoryTag,
     GeoDoubleParamsTag,
     GeoAsciiParamsTag,
@@ -142,6 +143,7 @@ fn decode_tag(tag: u16) -> IFDTag {
         532 => IFDTag::ReferenceBlackWhite,
         33550 => IFDTag::ModelPixelScaleTag,
         33922 => IFDTag::ModelTiepointTag,
+        34264 => IFDTag::ModelTransformationTag,
         GEO_KEY_DIRECTORY_TAG => IFDTag::GeoKeyDirectoryTag,
         GEO_DOUBLE_PARAMS_TAG => IFDTag::GeoDoubleParamsTag,
         GEO_ASCII_PARAMS_TAG => IFDTag::GeoAsciiParamsTag,
@@ -172,6 +174,7 @@ struct IFDEntryMetadata {
     pub field_type: IFDType,
     pub count: u64,
     pub offset_or_value: OffsetOrInlineValue,
+    pub endian: Endian,
 }
 
 #[cfg_attr(feature = "json", derive(serde::Serialize))]
@@ -188,22 +191,22 @@ enum RawEntryResult {
     InvalidCount(IFDTag, u64),
 }
 
-async fn read_u16(source: &mut Source, offset: u64) -> Result<u16, Error> {
+async fn read_u16(source: &mut Source, offset: u64, endian: Endian) -> Result<u16, Error> {
     let mut buf = [0u8; 2];
     source.read_exact(offset, &mut buf).await?;
-    Ok(decode_u16(buf))
+    Ok(decode_u16(buf, endian))
 }
 
-async fn read_u32(source: &mut Source, offset: u64) -> Result<u32, Error> {
+async fn read_u32(source: &mut Source, offset: u64, endian: Endian) -> Result<u32, Error> {
     let mut buf = [0u8; 4];
     source.read_exact(offset, &mut buf).await?;
-    Ok(decode_u32(buf))
+    Ok(decode_u32(buf, endian))
 }
 
-async fn read_u64(source: &mut Source, offset: u64) -> Result<u64, Error> {
+async fn read_u64(source: &mut Source, offset: u64, endian: Endian) -> Result<u64, Error> {
     let mut buf = [0u8; 8];
     source.read_exact(offset, &mut buf).await?;
-    Ok(decode_u64(buf))
+    Ok(decode_u64(buf, endian))
 }
 
 impl IFDEntryMetadata {
@@ -221,35 +224,52 @@ impl IFDEntryMetadata {
                 data
             }
         };
+        let endian = self.endian;
         let value = match self.field_type {
             IFDType::Byte => IFDValue::Byte(decode_vec(&data, self.count as usize, decode_u8)),
             IFDType::Ascii => IFDValue::Ascii(decode_string(&data)?),
-            IFDType::Short => IFDValue::Short(decode_vec(&data, self.count as usize, decode_u16)),
-            IFDType::Long => IFDValue::Long(decode_vec(&data, self.count as usize, decode_u32)),
-            IFDType::Rational => {
-                IFDValue::Rational(decode_vec(&data, self.count as usize, decode_u32_pair))
-            }
+            IFDType::Short => IFDValue::Short(decode_vec(&data, self.count as usize, |b| {
+                decode_u16(b, endian)
+            })),
+            IFDType::Long => IFDValue::Long(decode_vec(&data, self.count as usize, |b| {
+                decode_u32(b, endian)
+            })),
+            IFDType::Rational => IFDValue::Rational(decode_vec(&data, self.count as usize, |b| {
+                decode_u32_pair(b, endian)
+            })),
             IFDType::SignedByte => {
                 IFDValue::SignedByte(decode_vec(&data, self.count as usize, decode_i8))
             }
             IFDType::UndefinedRawBytes => IFDValue::UndefinedRawBytes(data),
             IFDType::SignedShort => {
-                IFDValue::SignedShort(decode_vec(&data, self.count as usize, decode_i16))
+                IFDValue::SignedShort(decode_vec(&data, self.count as usize, |b| {
+                    decode_i16(b, endian)
+                }))
             }
             IFDType::SignedLong => {
-                IFDValue::SignedLong(decode_vec(&data, self.count as usize, decode_i32))
+                IFDValue::SignedLong(decode_vec(&data, self.count as usize, |b| {
+                    decode_i32(b, endian)
+                }))
             }
             IFDType::SignedRational => {
-                IFDValue::SignedRational(decode_vec(&data, self.count as usize, decode_i32_pair))
+                IFDValue::SignedRational(decode_vec(&data, self.count as usize, |b| {
+                    decode_i32_pair(b, endian)
+                }))
             }
-            IFDType::Float => IFDValue::Float(decode_vec(&data, self.count as usize, decode_f32)),
-            IFDType::Double => IFDValue::Double(decode_vec(&data, self.count as usize, decode_f64)),
+            IFDType::Float => IFDValue::Float(decode_vec(&data, self.count as usize, |b| {
+                decode_f32(b, endian)
+            })),
+            IFDType::Double => IFDValue::Double(decode_vec(&data, self.count as usize, |b| {
+                decode_f64(b, endian)
+            })),
             IFDType::Unsigned64 => {
-                IFDValue::Unsigned64(decode_vec(&data, self.count as usize, decode_u64))
-            }
-            IFDType::Signed64 => {
-                IFDValue::Signed64(decode_vec(&data, self.count as usize, decode_u64))
+                IFDValue::Unsigned64(decode_vec(&data, self.count as usize, |b| {
+                    decode_u64(b, endian)
+                }))
             }
+            IFDType::Signed64 => IFDValue::Signed64(decode_vec(&data, self.count as usize, |b| {
+                decode_u64(b, endian)
+            })),
         };
         Ok(value)
     }
@@ -266,6 +286,16 @@ impl IFDEntryMetadata {
 #[cfg_attr(feature = "json", derive(serde::Serialize))]
 pub struct ImageFileDirectory {
     entries: Vec<IFDEntryMetadata>,
+    // File offset this IFD was read from, i.e. where its entry count starts. Used by
+    // `COG::validate` to check the COG spec's requirement that IFDs be laid out near the start of
+    // the file in increasing offset order, so a client can fetch them all with one small leading
+    // range read.
+    pub offset: u64,
+    // The TIFF's byte order mark, read once from the file header. `IFDEntryMetadata::read_value`
+    // already uses this per-entry to decode tag values - exposed here too so pixel/sample decoding
+    // (which happens well outside this module, in `data_types`/`image`/`predictor`) can check it
+    // against the raw tile bytes it reads directly from `Source`.
+    pub endian: Endian,
 }
 
 impl ImageFileDirectory {
@@ -352,22 +382,26 @@ enum TIFFVariant {
 }
 
 impl TIFFVariant {
-    async fn read_initial_ifd_offset(&self, source: &mut Source) -> Result<u64, Error> {
+    async fn read_initial_ifd_offset(
+        &self,
+        source: &mut Source,
+        endian: Endian,
+    ) -> Result<u64, Error> {
         match self {
-            TIFFVariant::Classic => Ok(read_u32(source, 4).await? as u64),
+            TIFFVariant::Classic => Ok(read_u32(source, 4, endian).await? as u64),
             TIFFVariant::BigTiff => {
-                let offset_bytesize = read_u16(source, 4).await?;
+                let offset_bytesize = read_u16(source, 4, endian).await?;
                 if offset_bytesize != 8 {
                     return Err(Error::InvalidData(format!(
                         "Invalid offset bytesize {}",
                         offset_bytesize
                     )));
                 }
-                let pad = read_u16(source, 6).await?;
+                let pad = read_u16(source, 6, endian).await?;
                 if pad != 0 {
                     return Err(Error::InvalidData(format!("Invalid pad {}", pad)));
                 }
-                Ok(read_u64(source, 8).await?)
+                Ok(read_u64(source, 8, endian).await?)
             }
         }
     }
@@ -390,14 +424,16 @@ impl TIFFVariant {
         &self,
         source: &mut Source,
         offset: u64,
+        endian: Endian,
     ) -> Result<(ImageFileDirectory, u64), Error> {
+        let ifd_offset = offset;
         let (fields_count, offset) = match self {
             TIFFVariant::Classic => (
-                read_u16(source, offset).await? as usize,
+                read_u16(source, offset, endian).await? as usize,
                 offset + size_of::<u16>() as u64,
             ),
             TIFFVariant::BigTiff => (
-                read_u64(source, offset).await? as usize,
+                read_u64(source, offset, endian).await? as usize,
                 offset + size_of::<u64>() as u64,
             ),
         };
@@ -409,7 +445,7 @@ impl TIFFVariant {
             let entry_start = i * self.ifd_entry_size();
             let entry_end = (i + 1) * self.ifd_entry_size();
             let buf: &[u8] = &ifd_data[entry_start..entry_end];
-            match self.decode_ifd_entry_metadata(buf).await? {
+            match self.decode_ifd_entry_metadata(buf, endian).await? {
                 RawEntryResult::KnownType(e) => entries.push(e),
                 RawEntryResult::UnknownType(tag, v) => {
                     println!("Unknown type for tag {:?}: {:?}", tag, v);
@@ -424,17 +460,30 @@ impl TIFFVariant {
                 ifd_data[fields_count * self.ifd_entry_size()..]
                     .try_into()
                     .unwrap(),
+                endian,
             ) as u64,
             TIFFVariant::BigTiff => decode_u64(
                 ifd_data[fields_count * self.ifd_entry_size()..]
                     .try_into()
                     .unwrap(),
+                endian,
             ),
         };
-        Ok((ImageFileDirectory { entries }, next_ifd_offset as u64))
+        Ok((
+            ImageFileDirectory {
+                entries,
+                offset: ifd_offset,
+                endian,
+            },
+            next_ifd_offset as u64,
+        ))
     }
 
-    async fn decode_ifd_entry_metadata(&self, buf: &[u8]) -> Result<RawEntryResult, Error> {
+    async fn decode_ifd_entry_metadata(
+        &self,
+        buf: &[u8],
+        endian: Endian,
+    ) -> Result<RawEntryResult, Error> {
         // Check buf len is correct
         let expected_len = match self {
             TIFFVariant::Classic => 12,
@@ -448,8 +497,8 @@ impl TIFFVariant {
             )));
         }
 
-        let tag = decode_tag(decode_u16([buf[0], buf[1]]));
-        let field_type = decode_u16([buf[2], buf[3]]);
+        let tag = decode_tag(decode_u16([buf[0], buf[1]], endian));
+        let field_type = decode_u16([buf[2], buf[3]], endian);
         let field_type = match field_type {
             0 => return Ok(RawEntryResult::UnknownType(tag, 0)),
             v @ 19.. => return Ok(RawEntryResult::UnknownType(tag, v)),
@@ -472,27 +521,29 @@ impl TIFFVariant {
         };
         let (count, offset_or_value) = match self {
             TIFFVariant::Classic => {
-                let count = decode_u32_from_slice(&buf[4..8]) as u64;
+                let count = decode_u32_from_slice(&buf[4..8], endian) as u64;
                 let offset_or_value: OffsetOrInlineValue = {
                     if type_size(field_type) * count as usize <= 4 {
                         OffsetOrInlineValue::FourBytesInlineValue([
                             buf[8], buf[9], buf[10], buf[11],
                         ])
                     } else {
-                        OffsetOrInlineValue::Offset(decode_u32_from_slice(&buf[8..12]) as u64)
+                        OffsetOrInlineValue::Offset(
+                            decode_u32_from_slice(&buf[8..12], endian) as u64
+                        )
                     }
                 };
                 (count, offset_or_value)
             }
             TIFFVariant::BigTiff => {
-                let count = decode_u64_from_slice(&buf[4..12]);
+                let count = decode_u64_from_slice(&buf[4..12], endian);
                 let offset_or_value: OffsetOrInlineValue = {
                     if type_size(field_type) * count as usize <= 8 {
                         let mut data = [0u8; 8];
                         data.copy_from_slice(&buf[12..20]);
                         OffsetOrInlineValue::EightBytesInlineValue(data)
                     } else {
-                        OffsetOrInlineValue::Offset(decode_u64_from_slice(&buf[12..20]))
+                        OffsetOrInlineValue::Offset(decode_u64_from_slice(&buf[12..20], endian))
                     }
                 };
                 (count, offset_or_value)
@@ -506,10 +557,17 @@ impl TIFFVariant {
             field_type,
             count,
             offset_or_value,
+            endian,
         }))
     }
 }
 
+/// Reads the IFD chain of either a classic (32-bit offset) or BigTIFF (64-bit offset, magic
+/// number 43) file - `open_from_source` picks the variant from the header, and every offset path
+/// from there on (`TIFFVariant::read_image_file_directory`, `IFDEntryMetadata`, `OffsetOrInlineValue`)
+/// is written against BigTIFF's wider fields, with classic TIFF values simply widened to `u64` on
+/// the way in. So a COG over 4 GiB already parses correctly here; the write side
+/// (`tiff::writer`) is classic-only so far.
 #[derive(Debug)]
 #[cfg_attr(feature = "json", derive(serde::Serialize))]
 pub struct TIFFReader {
@@ -525,22 +583,20 @@ impl TIFFReader {
         Ok(reader)
     }
     pub async fn open_from_source(mut source: Source) -> Result<TIFFReader, Error> {
-        // Byte order & magic number check
-        {
+        // Byte order mark
+        let endian: Endian = {
             let mut buf = [0u8; 2];
             source.read_exact(0, &mut buf[..]).await?;
             if buf[0] == 0x49 && buf[1] == 0x49 {
-                // Ok (little endian)
+                Ok(Endian::LittleEndian)
             } else if buf[0] == 0x4D && buf[1] == 0x4D {
-                return Err(Error::InvalidData(
-                    "Big endian files not supported".to_string(),
-                ));
+                Ok(Endian::BigEndian)
             } else {
-                return Err(Error::InvalidData(format!("Invalid byte_order {:?}", buf)));
+                Err(Error::InvalidData(format!("Invalid byte_order {:?}", buf)))
             }
-        }
+        }?;
         let variant: TIFFVariant = {
-            let magic_number = read_u16(&mut source, 2).await?;
+            let magic_number = read_u16(&mut source, 2, endian).await?;
             match magic_number {
                 42 => Ok(TIFFVariant::Classic),
                 43 => Ok(TIFFVariant::BigTiff),
@@ -551,7 +607,7 @@ impl TIFFReader {
             }
         }?;
 
-        let initial_ifd_offset: u64 = variant.read_initial_ifd_offset(&mut source).await?;
+        let initial_ifd_offset: u64 = variant.read_initial_ifd_offset(&mut source, endian).await?;
 
         // Read ifds
         let ifds: Vec<ImageFileDirectory> = {
@@ -560,7 +616,7 @@ impl TIFFReader {
             // TODO: Infinite loop detection ?
             while ifd_offset > 0 {
                 let (ifd, next_ifd_offset) = variant
-                    .read_image_file_directory(&mut source, ifd_offset)
+                    .read_image_file_directory(&mut source, ifd_offset, endian)
                     .await?;
                 ifd_offset = next_ifd_offset;
                 ifds.push(ifd);
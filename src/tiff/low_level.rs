@@ -2,38 +2,56 @@ use crate::errors::Error;
 /// Low-level byte conversion functions
 use std::mem::size_of;
 
+/// Byte order of the values stored in a TIFF file, detected from the byte-order mark at the
+/// start of the file ("II" for little endian / Intel, "MM" for big endian / Motorola).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub enum Endian {
+    LittleEndian,
+    BigEndian,
+}
+
 pub fn decode_u8(buf: [u8; 1]) -> u8 {
     u8::from_ne_bytes(buf)
 }
 
-pub fn decode_u16(buf: [u8; 2]) -> u16 {
-    u16::from_le_bytes(buf)
+pub fn decode_u16(buf: [u8; 2], endian: Endian) -> u16 {
+    match endian {
+        Endian::LittleEndian => u16::from_le_bytes(buf),
+        Endian::BigEndian => u16::from_be_bytes(buf),
+    }
 }
 
-pub fn decode_u32(buf: [u8; 4]) -> u32 {
-    u32::from_le_bytes(buf)
+pub fn decode_u32(buf: [u8; 4], endian: Endian) -> u32 {
+    match endian {
+        Endian::LittleEndian => u32::from_le_bytes(buf),
+        Endian::BigEndian => u32::from_be_bytes(buf),
+    }
 }
 
-pub fn decode_u32_from_slice(buf: &[u8]) -> u32 {
+pub fn decode_u32_from_slice(buf: &[u8], endian: Endian) -> u32 {
     let mut data = [0u8; 4];
     data.copy_from_slice(buf);
-    decode_u32(data)
+    decode_u32(data, endian)
 }
 
-pub fn decode_u64(buf: [u8; 8]) -> u64 {
-    u64::from_le_bytes(buf)
+pub fn decode_u64(buf: [u8; 8], endian: Endian) -> u64 {
+    match endian {
+        Endian::LittleEndian => u64::from_le_bytes(buf),
+        Endian::BigEndian => u64::from_be_bytes(buf),
+    }
 }
 
-pub fn decode_u64_from_slice(buf: &[u8]) -> u64 {
+pub fn decode_u64_from_slice(buf: &[u8], endian: Endian) -> u64 {
     let mut data = [0u8; 8];
     data.copy_from_slice(buf);
-    decode_u64(data)
+    decode_u64(data, endian)
 }
 
-pub fn decode_u32_pair(buf: [u8; 8]) -> (u32, u32) {
+pub fn decode_u32_pair(buf: [u8; 8], endian: Endian) -> (u32, u32) {
     (
-        decode_u32([buf[0], buf[1], buf[2], buf[3]]),
-        decode_u32([buf[4], buf[5], buf[6], buf[7]]),
+        decode_u32([buf[0], buf[1], buf[2], buf[3]], endian),
+        decode_u32([buf[4], buf[5], buf[6], buf[7]], endian),
     )
 }
 
@@ -41,27 +59,39 @@ pub fn decode_i8(buf: [u8; 1]) -> i8 {
     i8::from_ne_bytes(buf)
 }
 
-pub fn decode_i16(buf: [u8; 2]) -> i16 {
-    i16::from_le_bytes(buf)
+pub fn decode_i16(buf: [u8; 2], endian: Endian) -> i16 {
+    match endian {
+        Endian::LittleEndian => i16::from_le_bytes(buf),
+        Endian::BigEndian => i16::from_be_bytes(buf),
+    }
 }
 
-pub fn decode_i32(buf: [u8; 4]) -> i32 {
-    i32::from_le_bytes(buf)
+pub fn decode_i32(buf: [u8; 4], endian: Endian) -> i32 {
+    match endian {
+        Endian::LittleEndian => i32::from_le_bytes(buf),
+        Endian::BigEndian => i32::from_be_bytes(buf),
+    }
 }
 
-pub fn decode_i32_pair(buf: [u8; 8]) -> (i32, i32) {
+pub fn decode_i32_pair(buf: [u8; 8], endian: Endian) -> (i32, i32) {
     (
-        decode_i32([buf[0], buf[1], buf[2], buf[3]]),
-        decode_i32([buf[4], buf[5], buf[6], buf[7]]),
+        decode_i32([buf[0], buf[1], buf[2], buf[3]], endian),
+        decode_i32([buf[4], buf[5], buf[6], buf[7]], endian),
     )
 }
 
-pub fn decode_f32(buf: [u8; 4]) -> f32 {
-    f32::from_le_bytes(buf)
+pub fn decode_f32(buf: [u8; 4], endian: Endian) -> f32 {
+    match endian {
+        Endian::LittleEndian => f32::from_le_bytes(buf),
+        Endian::BigEndian => f32::from_be_bytes(buf),
+    }
 }
 
-pub fn decode_f64(buf: [u8; 8]) -> f64 {
-    f64::from_le_bytes(buf)
+pub fn decode_f64(buf: [u8; 8], endian: Endian) -> f64 {
+    match endian {
+        Endian::LittleEndian => f64::from_le_bytes(buf),
+        Endian::BigEndian => f64::from_be_bytes(buf),
+    }
 }
 
 pub fn decode_string(buf: &[u8]) -> Result<String, Error> {
@@ -0,0 +1,11 @@
+pub mod cog;
+pub mod compression;
+pub mod data_types;
+pub mod geo_keys;
+pub mod georef;
+pub mod ifd;
+pub mod low_level;
+pub mod predictor;
+pub mod proj;
+pub mod tags;
+pub mod writer;
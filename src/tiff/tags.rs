@@ -4,19 +4,50 @@ use super::ifd::{IFDTag, IFDValue, ImageFileDirectory};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PhotometricInterpretation {
+    // Grayscale where a sample of 0 is white rather than black - the inverse of `BlackIsZero`.
+    // Rare in practice (most encoders default to `BlackIsZero`), but still a valid TIFF value.
+    // `ImageBuffer::invert_grayscale` undoes it for display.
+    WhiteIsZero,
     BlackIsZero,
     Rgb,
+    Palette,
     Mask,
     YCbCr,
+    // CMYK. `BandsInterpretation::new` expects 4 data bands (plus an optional alpha, as for
+    // `Rgb`) and `ImageBuffer::cmyk_to_rgb` undoes it for display.
+    Separated,
+    // CIELab. Recognized so `PhotometricInterpretation::decode` doesn't reject files that use it,
+    // but there's no L*a*b*->RGB conversion here yet: `BandsInterpretation::new` rejects it with
+    // `Error::UnsupportedTagValue` rather than silently mis-displaying the samples as RGB.
+    CIELab,
 }
 
 impl PhotometricInterpretation {
+    /// The TIFF `PhotometricInterpretation` tag value (262) for this variant - the inverse of
+    /// `decode`.
+    pub fn encode(&self) -> u16 {
+        match self {
+            PhotometricInterpretation::WhiteIsZero => 0,
+            PhotometricInterpretation::BlackIsZero => 1,
+            PhotometricInterpretation::Rgb => 2,
+            PhotometricInterpretation::Palette => 3,
+            PhotometricInterpretation::Mask => 4,
+            PhotometricInterpretation::YCbCr => 6,
+            PhotometricInterpretation::Separated => 5,
+            PhotometricInterpretation::CIELab => 8,
+        }
+    }
+
     pub fn decode(v: u16) -> Result<PhotometricInterpretation, Error> {
         match v {
+            0 => Ok(PhotometricInterpretation::WhiteIsZero),
             1 => Ok(PhotometricInterpretation::BlackIsZero),
             2 => Ok(PhotometricInterpretation::Rgb),
+            3 => Ok(PhotometricInterpretation::Palette),
             4 => Ok(PhotometricInterpretation::Mask),
+            5 => Ok(PhotometricInterpretation::Separated),
             6 => Ok(PhotometricInterpretation::YCbCr),
+            8 => Ok(PhotometricInterpretation::CIELab),
             v => Err(Error::UnsupportedTagValue(
                 super::ifd::IFDTag::PhotometricInterpretation,
                 format!("{:?}", v),
@@ -46,3 +77,60 @@ impl PhotometricInterpretation {
         }
     }
 }
+
+/// The TIFF `ColorMap` tag (320): a lookup table from a single-band `PhotometricInterpretation::
+/// Palette` image's sample value to a 16-bit RGB color. The tag stores all red entries, then all
+/// green, then all blue, each sized `2^BitsPerSample`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorMap {
+    pub red: Vec<u16>,
+    pub green: Vec<u16>,
+    pub blue: Vec<u16>,
+}
+
+impl ColorMap {
+    pub async fn read_from_ifd(
+        source: &mut Source,
+        ifd: &ImageFileDirectory,
+    ) -> Result<ColorMap, Error> {
+        let values = ifd.get_vec_short_tag_value(source, IFDTag::Colormap).await?;
+        if values.len() % 3 != 0 {
+            return Err(Error::OtherError(format!(
+                "Colormap length {} is not a multiple of 3",
+                values.len()
+            )));
+        }
+        let n = values.len() / 3;
+        // The spec requires exactly 2^BitsPerSample entries per channel - a shorter table would
+        // let `lookup` index out of bounds on a valid (in-range) pixel value, so reject it here
+        // rather than bounds-checking (or silently clamping) on every lookup.
+        let bits_per_sample = ifd
+            .get_vec_short_tag_value(source, IFDTag::BitsPerSample)
+            .await?;
+        let expected_n = 1usize
+            .checked_shl(bits_per_sample.first().copied().unwrap_or(0) as u32)
+            .unwrap_or(usize::MAX);
+        if n != expected_n {
+            return Err(Error::OtherError(format!(
+                "Colormap has {} entries per channel, expected 2^BitsPerSample = {}",
+                n, expected_n
+            )));
+        }
+        Ok(ColorMap {
+            red: values[0..n].to_vec(),
+            green: values[n..2 * n].to_vec(),
+            blue: values[2 * n..3 * n].to_vec(),
+        })
+    }
+
+    /// Looks up the RGB color for palette index `idx`, scaling each channel down from the TIFF
+    /// 0-65535 range to 0-255. `read_from_ifd` validates `red`/`green`/`blue` each have
+    /// `2^BitsPerSample` entries, so any sample value decoded from this overview is in bounds.
+    pub fn lookup(&self, idx: usize) -> [u8; 3] {
+        [
+            (self.red[idx] >> 8) as u8,
+            (self.green[idx] >> 8) as u8,
+            (self.blue[idx] >> 8) as u8,
+        ]
+    }
+}
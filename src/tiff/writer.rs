@@ -0,0 +1,462 @@
+/// Writes an `ImageBuffer` + `Georeference` out as a tiled, (optionally multi-resolution) Cloud
+/// Optimized GeoTIFF - the write-side counterpart to the `ifd`/`cog` reading machinery. Only
+/// classic (32-bit offset), little-endian TIFF is produced; BigTIFF output isn't supported yet.
+///
+/// Tiling, the Raw/Deflate/PackBits/JPEG codecs, and chaining multiple IFDs into an overview
+/// pyramid are all covered here already (`write_level` does one IFD; `WriteOptions` controls tile
+/// size, compression, and JPEG quality). The Python bindings expose this through a `write_cog`
+/// function alongside their existing `read_tile` (see `python/src/lib.rs`).
+use tokio::io::AsyncWriteExt;
+
+use super::compression::{jpeg, Compression};
+use super::georef::{Georeference, Geotransform};
+use super::ifd::GEO_KEY_DIRECTORY_TAG;
+use super::tags::PhotometricInterpretation;
+use crate::epsg::{Crs, UnitOfMeasure};
+use crate::image::{DataType, ImageBuffer, ResampleAlg};
+use crate::Error;
+
+// TIFF field type ids (see the TIFF6 spec, table 2)
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+const TYPE_UNDEFINED: u16 = 7;
+const TYPE_DOUBLE: u16 = 12;
+
+// TIFF tag ids this writer emits. Kept local (rather than reusing `ifd::decode_tag`'s private
+// `IFDTag` enum, which has no encode direction) since the set we write is much smaller than the
+// set we can read.
+const TAG_IMAGE_WIDTH: u16 = 256;
+const TAG_IMAGE_LENGTH: u16 = 257;
+const TAG_BITS_PER_SAMPLE: u16 = 258;
+const TAG_COMPRESSION: u16 = 259;
+const TAG_PHOTOMETRIC_INTERPRETATION: u16 = 262;
+const TAG_SAMPLES_PER_PIXEL: u16 = 277;
+const TAG_PLANAR_CONFIGURATION: u16 = 284;
+const TAG_TILE_WIDTH: u16 = 322;
+const TAG_TILE_LENGTH: u16 = 323;
+const TAG_TILE_OFFSETS: u16 = 324;
+const TAG_TILE_BYTE_COUNTS: u16 = 325;
+const TAG_EXTRA_SAMPLES: u16 = 338;
+const TAG_SAMPLE_FORMAT: u16 = 339;
+const TAG_JPEG_TABLES: u16 = 347;
+const TAG_MODEL_PIXEL_SCALE: u16 = 33550;
+const TAG_MODEL_TIEPOINT: u16 = 33922;
+const TAG_MODEL_TRANSFORMATION: u16 = 34264;
+
+/// Controls how `COG::write` lays out the output file.
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    /// Width/height of each tile. Must be a multiple of 16 per the TIFF spec.
+    pub tile_size: u32,
+    pub compression: Compression,
+    /// When true, keeps halving resolution (bilinear-resampled) and appending another IFD until
+    /// one tile covers the whole image, following the same "overviews[0] is full resolution"
+    /// convention `COG::open` expects when reading the file back.
+    pub generate_overviews: bool,
+    /// JPEG quality (1-100), used only when `compression` is `Compression::Jpeg(..)`.
+    pub jpeg_quality: u8,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            tile_size: 512,
+            compression: Compression::Deflate,
+            generate_overviews: true,
+            jpeg_quality: 85,
+        }
+    }
+}
+
+// A single not-yet-placed IFD tag: already encoded to raw bytes, in native (little-endian) byte
+// order, but not yet knowing whether it'll be inlined into the directory entry or appended after
+// it as an out-of-line value.
+struct RawEntry {
+    tag: u16,
+    field_type: u16,
+    count: u32,
+    data: Vec<u8>,
+}
+
+fn push_short(entries: &mut Vec<RawEntry>, tag: u16, values: &[u16]) {
+    entries.push(RawEntry {
+        tag,
+        field_type: TYPE_SHORT,
+        count: values.len() as u32,
+        data: values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+    });
+}
+
+fn push_long(entries: &mut Vec<RawEntry>, tag: u16, values: &[u32]) {
+    entries.push(RawEntry {
+        tag,
+        field_type: TYPE_LONG,
+        count: values.len() as u32,
+        data: values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+    });
+}
+
+fn push_double(entries: &mut Vec<RawEntry>, tag: u16, values: &[f64]) {
+    entries.push(RawEntry {
+        tag,
+        field_type: TYPE_DOUBLE,
+        count: values.len() as u32,
+        data: values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+    });
+}
+
+fn push_undefined(entries: &mut Vec<RawEntry>, tag: u16, data: Vec<u8>) {
+    entries.push(RawEntry {
+        tag,
+        field_type: TYPE_UNDEFINED,
+        count: data.len() as u32,
+        data,
+    });
+}
+
+/// Appends `value` to `body` at an even offset (TIFF values must start on a word boundary),
+/// returning its absolute offset.
+fn append_aligned(body: &mut Vec<u8>, value: &[u8]) -> u32 {
+    if body.len() % 2 != 0 {
+        body.push(0);
+    }
+    let offset = body.len() as u32;
+    body.extend_from_slice(value);
+    offset
+}
+
+/// Lays out and writes one IFD plus its out-of-line tag values into `body`, which must already be
+/// positioned where the directory should start. `entries` is sorted by tag, as classic TIFF
+/// requires. Returns the absolute offset of this directory's `next_ifd_offset` placeholder, so
+/// the caller can back-patch it once it knows where (or whether) the next IFD starts, plus the
+/// absolute offset of the `TileOffsets` entry's out-of-line value, so the caller can back-patch it
+/// once the tile data that follows has actually been written and its offsets are known.
+fn write_ifd(body: &mut Vec<u8>, mut entries: Vec<RawEntry>) -> (usize, usize) {
+    entries.sort_by_key(|e| e.tag);
+
+    let directory_offset = body.len();
+    let header_size = 2 + entries.len() * 12 + 4;
+    body.extend(std::iter::repeat(0u8).take(header_size));
+
+    let mut tile_offsets_patch_pos = 0;
+    body[directory_offset..directory_offset + 2]
+        .copy_from_slice(&(entries.len() as u16).to_le_bytes());
+    for (i, entry) in entries.iter().enumerate() {
+        let entry_offset = directory_offset + 2 + i * 12;
+        body[entry_offset..entry_offset + 2].copy_from_slice(&entry.tag.to_le_bytes());
+        body[entry_offset + 2..entry_offset + 4].copy_from_slice(&entry.field_type.to_le_bytes());
+        body[entry_offset + 4..entry_offset + 8].copy_from_slice(&entry.count.to_le_bytes());
+
+        let value_or_offset = if entry.data.len() <= 4 {
+            let mut inline = [0u8; 4];
+            inline[..entry.data.len()].copy_from_slice(&entry.data);
+            inline
+        } else {
+            let value_offset = append_aligned(body, &entry.data);
+            if entry.tag == TAG_TILE_OFFSETS {
+                tile_offsets_patch_pos = value_offset as usize;
+            }
+            value_offset.to_le_bytes()
+        };
+        // The directory itself was already fully reserved above, so this always writes into
+        // already-allocated space rather than growing `body`.
+        body[entry_offset + 8..entry_offset + 12].copy_from_slice(&value_or_offset);
+    }
+
+    let next_ifd_offset_patch_pos = directory_offset + 2 + entries.len() * 12;
+    (next_ifd_offset_patch_pos, tile_offsets_patch_pos)
+}
+
+fn patch_u32(body: &mut [u8], pos: usize, value: u32) {
+    body[pos..pos + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn photometric_interpretation(image: &ImageBuffer, compression: &Compression) -> PhotometricInterpretation {
+    let color_bands = image.nbands - image.has_alpha as usize;
+    // `Compression::from_ifd`'s `jpeg_from_ifd` only accepts YCbCr for a JPEG-compressed IFD, so a
+    // 3-band JPEG tile must declare it here to be readable back - a 1-band JPEG tile, with no
+    // chroma channels to convert, stays BlackIsZero like any other single-band image.
+    if matches!(compression, Compression::Jpeg(_)) && color_bands == 3 {
+        PhotometricInterpretation::YCbCr
+    } else if color_bands == 3 {
+        PhotometricInterpretation::Rgb
+    } else {
+        PhotometricInterpretation::BlackIsZero
+    }
+}
+
+fn sample_format_and_bits(data_type: DataType) -> (u16, u16) {
+    match data_type {
+        DataType::Uint8 => (1, 8),
+        DataType::Uint16 => (1, 16),
+        DataType::Uint32 => (1, 32),
+        DataType::Int16 => (2, 16),
+        DataType::Int32 => (2, 32),
+        DataType::Float32 => (3, 32),
+        DataType::Float64 => (3, 64),
+    }
+}
+
+// The EPSG code to write for `crs`, mirroring `Crs::decode`/`Crs::proj_spec`'s read-side mapping.
+// `UserDefined` CRSes (built from GeoTIFF projection keys rather than a plain EPSG code) can't be
+// round-tripped yet - writing one back out would need emitting the full set of ProjCoordTransGeoKey
+// parameters, which isn't implemented.
+fn epsg_code_for_writing(crs: &Crs) -> Result<u16, Error> {
+    match crs {
+        Crs::PseudoMercator => Ok(3857),
+        Crs::Unknown(v) => Ok(*v),
+        Crs::UserDefined { .. } => Err(Error::UnsupportedProjection(
+            "Writing a user-defined (non-EPSG) CRS is not supported yet".to_string(),
+        )),
+    }
+}
+
+/// Builds the `GeoKeyDirectoryTag` shorts for `georeference`'s CRS/unit - just the handful of
+/// keys `Georeference::decode` actually reads back (`GTModelType`, `GTRasterType`, the
+/// CRS/linear-or-angular-unit pair), all of which fit inline (`TIFFTagLocation=0`) so no
+/// `GeoDoubleParamsTag`/`GeoAsciiParamsTag` is needed.
+fn geo_key_directory(georeference: &Georeference) -> Result<Vec<u16>, Error> {
+    let epsg_code = epsg_code_for_writing(&georeference.crs)?;
+    let (model_type, crs_key, unit_key, unit_code) = match georeference.unit {
+        UnitOfMeasure::LinearMeter => (1u16, 3072u16, 3076u16, 9001u16),
+        UnitOfMeasure::Degree => (2u16, 2048u16, 2054u16, 9102u16),
+    };
+    let mut keys = vec![1, 1, 0, 3]; // header: KeyDirectoryVersion, KeyRevision, MinorRevision, NumberOfKeys
+    keys.extend_from_slice(&[1024, 0, 1, model_type]); // GTModelType
+                                                       // GTRasterType: always RasterPixelIsArea (1), the only one `Georeference::decode` accepts
+    keys.extend_from_slice(&[1025, 0, 1, 1]);
+    keys.extend_from_slice(&[crs_key, 0, 1, epsg_code]); // ProjectedCRS/GeodeticCRS
+    keys.extend_from_slice(&[unit_key, 0, 1, unit_code]); // ProjLinearUnits/GeodeticAngularUnits
+    Ok(keys)
+}
+
+fn geo_transform_entries(entries: &mut Vec<RawEntry>, geo_transform: &Geotransform) {
+    if geo_transform.x_rotation == 0.0 && geo_transform.y_rotation == 0.0 {
+        push_double(
+            entries,
+            TAG_MODEL_PIXEL_SCALE,
+            &[geo_transform.x_res, -geo_transform.y_res, 0.0],
+        );
+        push_double(
+            entries,
+            TAG_MODEL_TIEPOINT,
+            &[0.0, 0.0, 0.0, geo_transform.ul_x, geo_transform.ul_y, 0.0],
+        );
+    } else {
+        // See `Geotransform::decode_from_matrix` for this layout
+        #[rustfmt::skip]
+        let matrix = [
+            geo_transform.x_res, geo_transform.x_rotation, 0.0, geo_transform.ul_x,
+            geo_transform.y_rotation, geo_transform.y_res, 0.0, geo_transform.ul_y,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ];
+        push_double(entries, TAG_MODEL_TRANSFORMATION, &matrix);
+    }
+}
+
+// One overview level's resolution, plus the `Geotransform` to write for it. `x_res`/`y_res`
+// scale with resolution (each pixel covers `scale`x the area), `ul_x`/`ul_y` are unaffected since
+// pixel (0, 0)'s upper-left corner doesn't move as resolution drops.
+fn overview_geotransform(full_res: &Geotransform, scale: f64) -> Geotransform {
+    Geotransform {
+        ul_x: full_res.ul_x,
+        ul_y: full_res.ul_y,
+        x_res: full_res.x_res * scale,
+        y_res: full_res.y_res * scale,
+        x_rotation: full_res.x_rotation * scale,
+        y_rotation: full_res.y_rotation * scale,
+    }
+}
+
+/// Writes one resolution level (tiled, compressed, with its own IFD) into `body`. Returns the
+/// absolute offset of this level's `next_ifd_offset` placeholder for the caller to back-patch.
+fn write_level(
+    body: &mut Vec<u8>,
+    image: &ImageBuffer,
+    geo_transform: Option<&Geotransform>,
+    geo_keys: Option<&[u16]>,
+    options: &WriteOptions,
+) -> Result<usize, Error> {
+    let tile_size = options.tile_size as usize;
+    let sample_bytes = image.data_type.size_bytes();
+    let tiles_across = image.width.div_ceil(tile_size);
+    let tiles_down = image.height.div_ceil(tile_size);
+
+    // JPEG's `JpegTables` tag is derived once (from the level's first tile) and shared by every
+    // other tile in the level - see `Compression::compress`'s doc comment for why this bypasses it
+    // and goes through `jpeg::Encoder` directly instead.
+    let jpeg_encoder = match &options.compression {
+        Compression::Jpeg(_) if image.data_type != DataType::Uint8 => {
+            return Err(Error::UnsupportedCompression(format!(
+                "JPEG writing only supports 8-bit samples, got {:?}",
+                image.data_type
+            )));
+        }
+        Compression::Jpeg(_) => Some(jpeg::Encoder::new(options.jpeg_quality)),
+        _ => None,
+    };
+    let mut jpeg_tables: Option<Vec<u8>> = None;
+
+    let mut tile_byte_counts = Vec::with_capacity(tiles_across * tiles_down);
+    let mut tile_blobs = Vec::with_capacity(tiles_across * tiles_down);
+    let mut tile_buf = vec![0u8; tile_size * tile_size * image.nbands * sample_bytes];
+    for tile_row in 0..tiles_down {
+        for tile_col in 0..tiles_across {
+            tile_buf.fill(0);
+            let row_bytes = tile_size * image.nbands * sample_bytes;
+            let src_rows = tile_size.min(image.height - tile_row * tile_size);
+            let src_cols = tile_size.min(image.width - tile_col * tile_size);
+            let copy_bytes = src_cols * image.nbands * sample_bytes;
+            for r in 0..src_rows {
+                let src_row = tile_row * tile_size + r;
+                let src_offset =
+                    (src_row * image.width + tile_col * tile_size) * image.nbands * sample_bytes;
+                let dst_offset = r * row_bytes;
+                tile_buf[dst_offset..dst_offset + copy_bytes]
+                    .copy_from_slice(&image.data[src_offset..src_offset + copy_bytes]);
+            }
+            let compressed = if let Some(encoder) = &jpeg_encoder {
+                let encoded = encoder.encode(&tile_buf, tile_size, tile_size, image.nbands)?;
+                jpeg_tables.get_or_insert(encoded.tables);
+                encoded.abbreviated_image
+            } else {
+                options.compression.compress(&tile_buf)?
+            };
+            tile_byte_counts.push(compressed.len() as u32);
+            tile_blobs.push(compressed);
+        }
+    }
+
+    let (sample_format, bits_per_sample) = sample_format_and_bits(image.data_type);
+    let extra_samples: Vec<u16> = if image.has_alpha { vec![2] } else { vec![] };
+
+    let mut entries = Vec::new();
+    push_long(&mut entries, TAG_IMAGE_WIDTH, &[image.width as u32]);
+    push_long(&mut entries, TAG_IMAGE_LENGTH, &[image.height as u32]);
+    push_short(
+        &mut entries,
+        TAG_BITS_PER_SAMPLE,
+        &vec![bits_per_sample; image.nbands],
+    );
+    push_short(
+        &mut entries,
+        TAG_COMPRESSION,
+        &[options.compression.tag_value()],
+    );
+    push_short(
+        &mut entries,
+        TAG_PHOTOMETRIC_INTERPRETATION,
+        &[photometric_interpretation(image, &options.compression).encode()],
+    );
+    push_short(&mut entries, TAG_SAMPLES_PER_PIXEL, &[image.nbands as u16]);
+    push_short(&mut entries, TAG_PLANAR_CONFIGURATION, &[1]);
+    push_long(&mut entries, TAG_TILE_WIDTH, &[options.tile_size]);
+    push_long(&mut entries, TAG_TILE_LENGTH, &[options.tile_size]);
+    push_long(
+        &mut entries,
+        TAG_TILE_OFFSETS,
+        &vec![0u32; tile_blobs.len()],
+    );
+    push_long(&mut entries, TAG_TILE_BYTE_COUNTS, &tile_byte_counts);
+    if !extra_samples.is_empty() {
+        push_short(&mut entries, TAG_EXTRA_SAMPLES, &extra_samples);
+    }
+    push_short(
+        &mut entries,
+        TAG_SAMPLE_FORMAT,
+        &vec![sample_format; image.nbands],
+    );
+    if let Some(tables) = jpeg_tables {
+        push_undefined(&mut entries, TAG_JPEG_TABLES, tables);
+    }
+    if let Some(geo_transform) = geo_transform {
+        geo_transform_entries(&mut entries, geo_transform);
+    }
+    if let Some(geo_keys) = geo_keys {
+        push_short(&mut entries, GEO_KEY_DIRECTORY_TAG, geo_keys);
+    }
+
+    let (next_ifd_offset_patch_pos, tile_offsets_patch_pos) = write_ifd(body, entries);
+
+    let mut tile_offsets = Vec::with_capacity(tile_blobs.len());
+    for blob in &tile_blobs {
+        tile_offsets.push(append_aligned(body, blob));
+    }
+    for (i, offset) in tile_offsets.iter().enumerate() {
+        patch_u32(body, tile_offsets_patch_pos + i * 4, *offset);
+    }
+
+    Ok(next_ifd_offset_patch_pos)
+}
+
+fn encode_cog(
+    image: &ImageBuffer,
+    georeference: &Georeference,
+    options: &WriteOptions,
+) -> Result<Vec<u8>, Error> {
+    let geo_keys = geo_key_directory(georeference)?;
+
+    // Classic TIFF header: byte order mark ("II"), magic number 42, offset of the first IFD
+    let mut body = vec![0x49, 0x49, 42, 0];
+    body.extend_from_slice(&8u32.to_le_bytes());
+
+    // levels[0] is the full-resolution image (scale 1.0), matching the "overviews[0] is full
+    // resolution" convention `COG::open` enforces when reading the file back. Each subsequent
+    // level halves resolution until a single tile covers the whole image.
+    let mut levels = vec![(image.clone(), 1.0)];
+    if options.generate_overviews {
+        loop {
+            let (previous, previous_scale) = levels.last().unwrap();
+            if previous.width.max(previous.height) <= options.tile_size as usize {
+                break;
+            }
+            let width = (previous.width / 2).max(1);
+            let height = (previous.height / 2).max(1);
+            let scale = previous_scale * 2.0;
+            levels.push((
+                previous.resample_to(width, height, ResampleAlg::Bilinear),
+                scale,
+            ));
+        }
+    }
+
+    let mut next_ifd_patch_pos = None;
+    for (i, (level, scale)) in levels.iter().enumerate() {
+        if let Some(pos) = next_ifd_patch_pos {
+            patch_u32(&mut body, pos, body.len() as u32);
+        }
+        let geo_transform = overview_geotransform(&georeference.geo_transform, *scale);
+        let pos = write_level(
+            &mut body,
+            level,
+            Some(&geo_transform),
+            Some(&geo_keys),
+            options,
+        )?;
+        next_ifd_patch_pos = if i + 1 < levels.len() {
+            Some(pos)
+        } else {
+            None
+        };
+    }
+    if let Some(pos) = next_ifd_patch_pos {
+        patch_u32(&mut body, pos, 0);
+    }
+
+    Ok(body)
+}
+
+/// Writes `image`/`georeference` out to `path` as a (Cloud Optimized) GeoTIFF per `options`. See
+/// `WriteOptions` for what's configurable; `COG::write` is the public entry point.
+pub async fn write(
+    path: &str,
+    image: &ImageBuffer,
+    georeference: &Georeference,
+    options: &WriteOptions,
+) -> Result<(), Error> {
+    let body = encode_cog(image, georeference, options)?;
+    let mut file = tokio::fs::File::create(path).await?;
+    file.write_all(&body).await?;
+    Ok(())
+}
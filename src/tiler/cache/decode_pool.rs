@@ -0,0 +1,278 @@
+// Background decode pipeline for the raw (pre-warp) TIFF tiles `OverviewDataReader` reads one at a
+// time in its tile loop - distinct from `LruTileCache`, which caches already-warped output XYZ
+// tiles. Panning/zooming around a region touches the same underlying TIFF tiles repeatedly at
+// different output resolutions/resampling kernels, so caching *these* avoids re-fetching and
+// re-decompressing them even when the output tile cache above it misses.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex as StdMutex};
+
+use quick_cache::sync::Cache;
+use tokio::sync::{mpsc, oneshot, Mutex as TokioMutex, Semaphore};
+
+use crate::sources::Source;
+use crate::tiff::compression::Compression;
+use crate::tiff::predictor::Predictor;
+use crate::Error;
+
+/// Identifies one raw TIFF tile: the overview it belongs to (`ifd_offset`, the IFD's own on-disk
+/// offset - stable for a given overview the way `COGValidationReport` already relies on it being)
+/// and its row/column in that overview's tile grid.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct TileKey {
+    pub ifd_offset: u64,
+    pub tile_i: u64,
+    pub tile_j: u64,
+}
+
+impl TileKey {
+    fn scratch_file_name(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        format!("{:016x}.tile", hasher.finish())
+    }
+}
+
+// Everything a background worker needs to turn one already-fetched compressed tile into decoded,
+// unpacked sample bytes. A plain data bundle rather than a closure over `&mut Source`/`&mut COG`:
+// the worker runs on a blocking-pool thread, and `Source` isn't safe to share across threads
+// without its own synchronization, so `DecodeTilePool::get_tile` does the fetch on the calling
+// task and only hands the pool the bytes already read plus enough metadata to decode them.
+struct DecodeJob {
+    compressed: Vec<u8>,
+    compression: Compression,
+    predictor: Predictor,
+    tile_width: usize,
+    tile_height: usize,
+    nbands: usize,
+    bit_depth: usize,
+    reply: oneshot::Sender<Result<Vec<u8>, Error>>,
+}
+
+fn run_decode_job(job: DecodeJob) {
+    let result = (|| -> Result<Vec<u8>, Error> {
+        // Rows are individually padded to a byte boundary, so for sub-byte sample depths the
+        // packed tile size isn't simply width*height*nbands*bits/8 - same computation
+        // `OverviewDataReader::read_image_part_into` does before calling `decompress_into`.
+        let expected_packed_tile_bytes =
+            (job.tile_width * job.nbands * job.bit_depth).div_ceil(8) * job.tile_height;
+        let mut decompressed = Vec::new();
+        job.compression.decompress_into(
+            &job.compressed,
+            job.tile_width,
+            job.tile_height,
+            expected_packed_tile_bytes,
+            &mut decompressed,
+        )?;
+        job.predictor
+            .reconstruct(decompressed, job.tile_width, job.nbands, job.bit_depth)
+    })();
+    // The only way this send fails is if `get_tile` already gave up on its `reply_rx.await`
+    // (e.g. the caller was cancelled), in which case there's nothing left to deliver the result
+    // to.
+    let _ = job.reply.send(result);
+}
+
+/// Bounded background decode pool for raw TIFF tiles, with an in-memory LRU layer backed by an
+/// on-disk scratch cache for the larger working set that doesn't fit in memory.
+///
+/// `get_tile` checks memory, then scratch, then - on a full miss - fetches the compressed tile via
+/// `source` on the calling task and hands the CPU-bound decompress+predictor work to the pool's
+/// workers. The pool bounds its own memory use two ways: `channel_capacity` caps how many fetched-
+/// but-not-yet-decoded tiles' compressed bytes can be queued at once (`request_tx.send` awaits once
+/// full, applying backpressure to callers), and `num_workers` (via a `Semaphore`) caps how many
+/// decodes run concurrently, so at most `channel_capacity + num_workers` tiles' worth of
+/// intermediate buffers are resident at a time on top of the `max_memory_tiles`-bounded result
+/// cache.
+pub struct DecodeTilePool {
+    request_tx: mpsc::Sender<DecodeJob>,
+    memory: Cache<TileKey, Arc<Vec<u8>>>,
+    scratch_dir: PathBuf,
+    scratch_max_bytes: u64,
+    scratch_write_locks: Arc<StdMutex<HashMap<String, Arc<TokioMutex<()>>>>>,
+}
+
+impl DecodeTilePool {
+    pub fn new(
+        num_workers: usize,
+        channel_capacity: usize,
+        max_memory_tiles: usize,
+        scratch_dir: PathBuf,
+        scratch_max_bytes: u64,
+    ) -> DecodeTilePool {
+        let (request_tx, mut request_rx) = mpsc::channel::<DecodeJob>(channel_capacity);
+        let semaphore = Arc::new(Semaphore::new(num_workers.max(1)));
+        tokio::spawn(async move {
+            while let Some(job) = request_rx.recv().await {
+                // Acquiring the permit before `spawn_blocking` (rather than inside it) is what
+                // actually bounds concurrency - once `num_workers` decodes are running, this await
+                // blocks the dispatch loop, which in turn leaves jobs queued in `request_tx`
+                // (itself bounded) instead of spawning unboundedly many blocking tasks.
+                let Ok(permit) = semaphore.clone().acquire_owned().await else {
+                    break;
+                };
+                tokio::task::spawn_blocking(move || {
+                    run_decode_job(job);
+                    drop(permit);
+                });
+            }
+        });
+        DecodeTilePool {
+            request_tx,
+            memory: Cache::new(max_memory_tiles.max(1)),
+            scratch_dir,
+            scratch_max_bytes,
+            scratch_write_locks: Arc::new(StdMutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns `key`'s decoded, predictor-reversed, still-packed tile bytes - from memory, then
+    /// scratch, then by fetching `byte_count` bytes at `offset` from `source` and scheduling a
+    /// decode on the pool. `compression`/`predictor`/`tile_width`/`tile_height`/`nbands`/
+    /// `bit_depth` describe `key`'s overview, the same way they're threaded through
+    /// `OverviewDataReader::read_image_part_into`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_tile(
+        &self,
+        key: TileKey,
+        source: &mut Source,
+        offset: u64,
+        byte_count: u64,
+        compression: Compression,
+        predictor: Predictor,
+        tile_width: usize,
+        tile_height: usize,
+        nbands: usize,
+        bit_depth: usize,
+    ) -> Result<Arc<Vec<u8>>, Error> {
+        if let Some(tile) = self.memory.get(&key) {
+            return Ok(tile);
+        }
+        if let Some(tile) = self.read_scratch(&key).await {
+            let tile = Arc::new(tile);
+            self.memory.insert(key, tile.clone());
+            return Ok(tile);
+        }
+
+        let mut compressed = vec![0u8; byte_count as usize];
+        source.read_exact_direct(offset, &mut compressed).await?;
+
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let job = DecodeJob {
+            compressed,
+            compression,
+            predictor,
+            tile_width,
+            tile_height,
+            nbands,
+            bit_depth,
+            reply: reply_tx,
+        };
+        self.request_tx
+            .send(job)
+            .await
+            .map_err(|_| Error::OtherError("DecodeTilePool's worker loop has shut down".to_string()))?;
+        let tile = reply_rx.await.map_err(|_| {
+            Error::OtherError("DecodeTilePool worker dropped its reply".to_string())
+        })??;
+
+        self.write_scratch(&key, &tile).await;
+        let tile = Arc::new(tile);
+        self.memory.insert(key, tile.clone());
+        Ok(tile)
+    }
+
+    fn scratch_path(&self, key: &TileKey) -> PathBuf {
+        self.scratch_dir.join(key.scratch_file_name())
+    }
+
+    // Reading the file is what keeps its access time fresh for `evict_scratch_until_within_budget`
+    // (best-effort: filesystems mounted `noatime` won't update it, so eviction just gets less
+    // precise there) - same tradeoff `DiskChunkCache::read` makes for compressed source chunks.
+    async fn read_scratch(&self, key: &TileKey) -> Option<Vec<u8>> {
+        tokio::fs::read(self.scratch_path(key)).await.ok()
+    }
+
+    async fn write_scratch(&self, key: &TileKey, data: &[u8]) {
+        let file_name = key.scratch_file_name();
+        let lock = {
+            let mut locks = self.scratch_write_locks.lock().unwrap();
+            locks
+                .entry(file_name.clone())
+                .or_insert_with(|| Arc::new(TokioMutex::new(())))
+                .clone()
+        };
+        {
+            let _guard = lock.lock().await;
+            self.write_scratch_locked(&file_name, data).await;
+        }
+        // Drop our clone before checking the map's own refcount: a count of 1 there means the map
+        // entry is the only remaining reference (nobody else is concurrently holding or waiting on
+        // this tile's lock), so it's safe to prune - otherwise `scratch_write_locks` would grow by
+        // one entry per distinct tile key for the life of the process.
+        drop(lock);
+        let mut locks = self.scratch_write_locks.lock().unwrap();
+        if locks.get(&file_name).map(Arc::strong_count) == Some(1) {
+            locks.remove(&file_name);
+        }
+    }
+
+    async fn write_scratch_locked(&self, file_name: &str, data: &[u8]) {
+        let path = self.scratch_dir.join(file_name);
+        if tokio::fs::metadata(&path).await.is_ok() {
+            // Someone else already spilled this tile while we were waiting for the lock.
+            return;
+        }
+        if tokio::fs::create_dir_all(&self.scratch_dir).await.is_err() {
+            return;
+        }
+        let tmp_path = self
+            .scratch_dir
+            .join(format!("{}.tmp.{}", file_name, std::process::id()));
+        if tokio::fs::write(&tmp_path, data).await.is_err() {
+            return;
+        }
+        let _ = tokio::fs::rename(&tmp_path, &path).await;
+
+        self.evict_scratch_until_within_budget().await;
+    }
+
+    // Same opportunistic, access-time-ordered eviction `DiskChunkCache::evict_until_within_budget`
+    // uses for compressed source chunks - run after each write rather than on a timer, trading
+    // perfect budget adherence for a much simpler implementation.
+    async fn evict_scratch_until_within_budget(&self) {
+        let mut entries = match tokio::fs::read_dir(&self.scratch_dir).await {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        let mut files = Vec::new();
+        let mut total_bytes: u64 = 0;
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            let accessed = metadata
+                .accessed()
+                .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+            total_bytes += metadata.len();
+            files.push((entry.path(), accessed, metadata.len()));
+        }
+        if total_bytes <= self.scratch_max_bytes {
+            return;
+        }
+        files.sort_by_key(|(_, accessed, _)| *accessed);
+        for (path, _, len) in files {
+            if total_bytes <= self.scratch_max_bytes {
+                break;
+            }
+            if tokio::fs::remove_file(&path).await.is_ok() {
+                total_bytes = total_bytes.saturating_sub(len);
+            }
+        }
+    }
+}
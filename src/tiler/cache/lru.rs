@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use quick_cache::sync::Cache;
+use quick_cache::Weighter;
+
+use crate::tiler::{extract_tile, TMSTileCoords, TileData};
+use crate::{Error, COG};
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Key {
+    // Caller-supplied identity for the COG being read (e.g. its source spec) - `extract_tile`
+    // itself has no notion of "which file", so this is what lets one `LruTileCache` be shared
+    // across a tile server's whole fleet of open COGs instead of one cache per file.
+    cog_id: String,
+    z: u32,
+    x: u64,
+    y: u64,
+}
+
+// `find_best_overview` is a pure function of `(tms, z)`, so keying on `z` alone already pins down
+// which overview a hit came from for a given COG - there's no need (and no way, before extracting)
+// to put the overview index itself in the key.
+struct Entry {
+    tile: Arc<TileData>,
+    byte_size: u64,
+    inserted_at: Instant,
+    hit_count: AtomicU64,
+    last_access: Mutex<Instant>,
+}
+
+#[derive(Clone)]
+struct TileWeighter;
+
+impl Weighter<Key, Arc<Entry>> for TileWeighter {
+    fn weight(&self, _key: &Key, entry: &Arc<Entry>) -> u64 {
+        entry.byte_size.max(1)
+    }
+}
+
+/// Per-tile access counters for one cached entry, as returned by `LruTileCache::stats` - lets an
+/// operator see which tiles are hot and tune `LruTileCache::new`'s byte budget accordingly.
+pub struct TileCacheStats {
+    pub z: u32,
+    pub x: u64,
+    pub y: u64,
+    pub byte_size: u64,
+    pub hit_count: u64,
+    pub last_access: Instant,
+}
+
+/// An in-memory, bounded LRU cache of decoded `TileData`, sitting in front of `extract_tile` so a
+/// tile server hammering the same low-zoom tiles doesn't re-issue ranged reads and re-decode them
+/// on every request. Entries are weighed by their decoded byte size (`img.data` + `mask`) against
+/// `max_bytes`, and expire after `ttl` regardless of how hot they are, so a long-lived server
+/// doesn't serve a COG's tiles forever after the underlying file has changed.
+///
+/// This is a thin, optional layer: call `extract_tile` directly (as the exact-byte comparison
+/// tests in `tiler::tests` do) to bypass it entirely.
+pub struct LruTileCache {
+    cache: Cache<Key, Arc<Entry>, TileWeighter>,
+    ttl: Duration,
+}
+
+impl LruTileCache {
+    pub fn new(max_bytes: u64, ttl: Duration) -> LruTileCache {
+        LruTileCache {
+            cache: Cache::with_weighter(1024, max_bytes, TileWeighter),
+            ttl,
+        }
+    }
+
+    /// Returns `cog_id`/`tile_coords`'s tile, extracting and caching it on a miss or an
+    /// expired entry. `cog_id` should identify `cog` uniquely (e.g. the source spec it was opened
+    /// from) - the cache doesn't otherwise know which COG `cog` is.
+    pub async fn get_or_extract(
+        &self,
+        cog: &mut COG,
+        cog_id: &str,
+        tile_coords: TMSTileCoords,
+    ) -> Result<Arc<TileData>, Error> {
+        let key = Key {
+            cog_id: cog_id.to_string(),
+            z: tile_coords.z,
+            x: tile_coords.x,
+            y: tile_coords.y,
+        };
+        if let Some(entry) = self.cache.get(&key) {
+            if entry.inserted_at.elapsed() < self.ttl {
+                entry.hit_count.fetch_add(1, Ordering::Relaxed);
+                *entry.last_access.lock().unwrap() = Instant::now();
+                return Ok(entry.tile.clone());
+            }
+        }
+
+        let tile = extract_tile(cog, tile_coords).await?;
+        let byte_size = (tile.img.data.len() + tile.mask.len()) as u64;
+        let entry = Arc::new(Entry {
+            tile: Arc::new(tile),
+            byte_size,
+            inserted_at: Instant::now(),
+            hit_count: AtomicU64::new(0),
+            last_access: Mutex::new(Instant::now()),
+        });
+        self.cache.insert(key, entry.clone());
+        Ok(entry.tile.clone())
+    }
+
+    /// Access stats for every entry currently cached (including ones past their TTL, which are
+    /// only evicted lazily on their next lookup).
+    pub fn stats(&self) -> Vec<TileCacheStats> {
+        self.cache
+            .iter()
+            .map(|(key, entry)| TileCacheStats {
+                z: key.z,
+                x: key.x,
+                y: key.y,
+                byte_size: entry.byte_size,
+                hit_count: entry.hit_count.load(Ordering::Relaxed),
+                last_access: *entry.last_access.lock().unwrap(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,78 @@
+use super::TileCache;
+use crate::Error;
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::Mutex;
+
+/// MBTiles (https://github.com/mapbox/mbtiles-spec) cache backend: a SQLite file with a
+/// `tiles(zoom_level, tile_column, tile_row, tile_data)` table - `tile_row` stored in the TMS
+/// convention the spec requires (row 0 at the south edge), unlike the XYZ convention `TileCache`
+/// is addressed in - plus a `metadata` key/value table for the usual MBTiles metadata fields
+/// (`name`, `bounds`, `minzoom`, `maxzoom`, `format`, ...).
+pub struct MBTilesCache {
+    conn: Mutex<Connection>,
+}
+
+impl MBTilesCache {
+    pub fn open(path: &str) -> Result<MBTilesCache, Error> {
+        let conn = Connection::open(path).map_err(|e| {
+            Error::OtherError(format!("Failed to open MBTiles file {}: {}", path, e))
+        })?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS tiles (
+                zoom_level INTEGER,
+                tile_column INTEGER,
+                tile_row INTEGER,
+                tile_data BLOB,
+                PRIMARY KEY (zoom_level, tile_column, tile_row)
+            );
+            CREATE TABLE IF NOT EXISTS metadata (name TEXT PRIMARY KEY, value TEXT);",
+        )
+        .map_err(|e| Error::OtherError(format!("Failed to initialize MBTiles schema: {}", e)))?;
+        Ok(MBTilesCache {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn set_metadata(&self, name: &str, value: &str) -> Result<(), Error> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO metadata (name, value) VALUES (?1, ?2)",
+            params![name, value],
+        )
+        .map_err(|e| Error::OtherError(format!("Failed to write MBTiles metadata: {}", e)))?;
+        Ok(())
+    }
+
+    // As per the MBTiles spec, `tile_row` uses the TMS convention (row 0 at the south edge)
+    fn tms_row(z: u32, y: u64) -> u64 {
+        (1u64 << z) - 1 - y
+    }
+}
+
+#[async_trait]
+impl TileCache for MBTilesCache {
+    async fn get(&self, z: u32, x: u64, y: u64) -> Result<Option<Vec<u8>>, Error> {
+        let tile_row = Self::tms_row(z, y);
+        let conn = self.conn.lock().unwrap();
+        // SQLite integers are signed 64-bit, so store tile indices as i64 rather than u64
+        conn.query_row(
+            "SELECT tile_data FROM tiles WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3",
+            params![z as i64, x as i64, tile_row as i64],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| Error::OtherError(format!("Failed to read tile from MBTiles cache: {}", e)))
+    }
+
+    async fn put(&self, z: u32, x: u64, y: u64, data: &[u8]) -> Result<(), Error> {
+        let tile_row = Self::tms_row(z, y);
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO tiles (zoom_level, tile_column, tile_row, tile_data) VALUES (?1, ?2, ?3, ?4)",
+            params![z as i64, x as i64, tile_row as i64, data],
+        )
+        .map_err(|e| Error::OtherError(format!("Failed to write tile to MBTiles cache: {}", e)))?;
+        Ok(())
+    }
+}
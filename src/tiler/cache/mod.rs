@@ -0,0 +1,111 @@
+/// Pluggable cache for already-encoded tile bytes (e.g. PNG/JPEG), consulted by the tiler before
+/// falling back to `extract_tile`. Tiles are addressed in the usual XYZ "slippy map" convention
+/// (y increasing south) - backends that store tiles in a different convention (e.g. MBTiles, which
+/// uses TMS) are responsible for converting internally.
+use crate::image::ImageBuffer;
+use crate::tiler::{extract_tile, TMSTileCoords};
+use crate::{Error, COG};
+use async_trait::async_trait;
+
+mod decode_pool;
+mod lru;
+mod mbtiles;
+pub use decode_pool::{DecodeTilePool, TileKey};
+pub use lru::{LruTileCache, TileCacheStats};
+pub use mbtiles::MBTilesCache;
+
+#[async_trait]
+pub trait TileCache: Send + Sync {
+    async fn get(&self, z: u32, x: u64, y: u64) -> Result<Option<Vec<u8>>, Error>;
+    async fn put(&self, z: u32, x: u64, y: u64, data: &[u8]) -> Result<(), Error>;
+}
+
+/// Consults `cache` for `(z, x, y)` before falling back to `extract_tile`, encoding (via `encode`)
+/// and storing the result on a miss. Returns the encoded tile bytes either way.
+pub async fn extract_tile_cached(
+    cog: &mut COG,
+    z: u32,
+    x: u64,
+    y: u64,
+    cache: &dyn TileCache,
+    encode: impl FnOnce(&ImageBuffer) -> Result<Vec<u8>, Error>,
+) -> Result<Vec<u8>, Error> {
+    if let Some(cached) = cache.get(z, x, y).await? {
+        return Ok(cached);
+    }
+    let tile = extract_tile(cog, TMSTileCoords::from_zxy(z, x, y)).await?;
+    let encoded = encode(&tile.img)?;
+    cache.put(z, x, y, &encoded).await?;
+    Ok(encoded)
+}
+
+// Converts a lon/lat point to fractional XYZ tile coordinates at `z`, per the usual Web Mercator
+// tile formula (https://wiki.openstreetmap.org/wiki/Slippy_map_tilenames#Lon..2Flat_to_tile_numbers_2).
+fn lnglat_to_tile_xy(lng: f64, lat: f64, z: u32) -> (f64, f64) {
+    let n = 2f64.powi(z as i32);
+    let x = (lng + 180.0) / 360.0 * n;
+    // Clamp away from the poles, where the Mercator projection is undefined
+    let lat_rad = lat.clamp(-85.051_128, 85.051_128).to_radians();
+    let y = (1.0 - (lat_rad.tan() + 1.0 / lat_rad.cos()).ln() / std::f64::consts::PI) / 2.0 * n;
+    (x, y)
+}
+
+// Returns the inclusive XYZ tile index range, at zoom `z`, covering `bounds` (a lon/lat box such
+// as `COG::lnglat_bounds` returns).
+pub(crate) fn xyz_tile_range(bounds: &crate::BoundingBox, z: u32) -> (u64, u64, u64, u64) {
+    let n = 1u64 << z;
+    let (x0, y0) = lnglat_to_tile_xy(bounds.xmin, bounds.ymax, z);
+    let (x1, y1) = lnglat_to_tile_xy(bounds.xmax, bounds.ymin, z);
+    let clamp = |v: f64| (v.floor() as i64).clamp(0, n as i64 - 1) as u64;
+    (clamp(x0), clamp(x1), clamp(y0), clamp(y1))
+}
+
+/// Seeds `cache` with every tile in `min_zoom..=max_zoom` intersecting `cog`'s `lnglat_bounds`.
+pub async fn warm(
+    cog: &mut COG,
+    cache: &dyn TileCache,
+    min_zoom: u32,
+    max_zoom: u32,
+    encode: impl Fn(&ImageBuffer) -> Result<Vec<u8>, Error>,
+) -> Result<(), Error> {
+    let bounds = cog.lnglat_bounds()?;
+    for z in min_zoom..=max_zoom {
+        let (x_from, x_to, y_from, y_to) = xyz_tile_range(&bounds, z);
+        for x in x_from..=x_to {
+            for y in y_from..=y_to {
+                extract_tile_cached(cog, z, x, y, cache, &encode).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Renders `cog`'s full tile pyramid across `min_zoom..=max_zoom` into a new MBTiles file at
+/// `path`, the way gdal2tiles would, but skipping any tile outside `cog`'s footprint instead of
+/// rendering (and storing) an empty one - `warm` already only touches tiles whose bounds intersect
+/// `lnglat_bounds`, via `xyz_tile_range`. `format` is the MBTiles `format` metadata value (e.g.
+/// `"png"`), matching whatever `encode` produces.
+pub async fn export_mbtiles(
+    cog: &mut COG,
+    path: &str,
+    min_zoom: u32,
+    max_zoom: u32,
+    format: &str,
+    encode: impl Fn(&ImageBuffer) -> Result<Vec<u8>, Error>,
+) -> Result<(), Error> {
+    let cache = MBTilesCache::open(path)?;
+    let bounds = cog.lnglat_bounds()?;
+    cache.set_metadata("name", path)?;
+    cache.set_metadata("format", format)?;
+    cache.set_metadata("type", "baselayer")?;
+    cache.set_metadata(
+        "bounds",
+        &format!(
+            "{},{},{},{}",
+            bounds.xmin, bounds.ymin, bounds.xmax, bounds.ymax
+        ),
+    )?;
+    cache.set_metadata("minzoom", &min_zoom.to_string())?;
+    cache.set_metadata("maxzoom", &max_zoom.to_string())?;
+    warm(cog, &cache, min_zoom, max_zoom, encode).await
+}
@@ -1,12 +1,15 @@
 use crate::bbox::BoundingBox;
 use crate::epsg::spheroid_3857::{EARTH_RADIUS_METERS, TOP_LEFT_METERS};
-use crate::image::ImageBuffer;
+use crate::epsg::UnitOfMeasure;
+use crate::image::{ImageBuffer, ResampleAlg};
 use crate::tiff::cog::ImageRect;
-use crate::tiff::georef::Georeference;
+use crate::tiff::georef::{lon_to_meters_equator, Georeference};
+use crate::tiff::tags::{ColorMap, PhotometricInterpretation};
 use crate::Error;
 use crate::COG;
 
 use self::warp::Warper;
+pub mod cache;
 mod warp;
 use crate::math::{vec2f, Vec2f};
 
@@ -35,6 +38,97 @@ pub struct TMSTileCoords {
 
 pub const TILE_SIZE: u64 = 256;
 
+/// Default `edge_padding` passed by `extract_tile` - enough overview pixels beyond the tile's
+/// footprint for `Resampling::Bilinear`/`Cubic` to have real neighbors at the tile boundary,
+/// matching rio-tiler's `tile_edge_padding` default.
+pub const DEFAULT_TILE_EDGE_PADDING: u64 = 2;
+
+/// Describes an output tile grid: its CRS, the pixel size of one tile, the coordinate of the
+/// south-west corner of tile `(0, 0)` at zoom 0, and the resolution at zoom 0 (which halves every
+/// zoom level, like any quadtree pyramid). This is this crate's equivalent of the OGC
+/// TileMatrixSet / morecantile abstraction - before it existed, `extract_tile` and `TMSTileCoords`
+/// were hard-wired to the WebMercatorQuad grid; `web_mercator()` below reproduces that exactly, so
+/// existing callers are unaffected.
+#[derive(Debug, Clone)]
+pub struct TileMatrixSet {
+    /// PROJ definition of the grid's CRS, e.g. `"EPSG:3857"`.
+    pub crs_proj_spec: String,
+    pub unit: UnitOfMeasure,
+    pub tile_size: u64,
+    /// Coordinate, in the grid CRS, of the south-west corner of tile `(x=0, y=0)` at zoom 0.
+    pub origin: (f64, f64),
+    /// Grid CRS units per pixel at zoom 0.
+    pub resolution_zoom0: f64,
+}
+
+impl TileMatrixSet {
+    /// The WebMercatorQuad grid (EPSG:3857): a single 256px root tile covering the whole
+    /// projected extent. This is what every tile-related function in this module assumed before
+    /// `TileMatrixSet` existed.
+    pub fn web_mercator() -> TileMatrixSet {
+        TileMatrixSet {
+            crs_proj_spec: "EPSG:3857".to_string(),
+            unit: UnitOfMeasure::LinearMeter,
+            tile_size: TILE_SIZE,
+            origin: TOP_LEFT_METERS,
+            resolution_zoom0: 2.0 * std::f64::consts::PI * EARTH_RADIUS_METERS / 256.0,
+        }
+    }
+
+    /// The OGC WorldCRS84Quad grid (EPSG:4326 plate-carrée): two root tiles side by side at zoom
+    /// 0, together covering -180..180 longitude and -90..90 latitude.
+    pub fn world_crs84_quad() -> TileMatrixSet {
+        TileMatrixSet {
+            crs_proj_spec: "EPSG:4326".to_string(),
+            unit: UnitOfMeasure::Degree,
+            tile_size: TILE_SIZE,
+            origin: (-180.0, -90.0),
+            resolution_zoom0: 180.0 / TILE_SIZE as f64,
+        }
+    }
+
+    /// Grid CRS units per pixel at the given zoom level.
+    pub fn resolution(&self, zoom: u32) -> f64 {
+        self.resolution_zoom0 / 2.0_f64.powf(zoom as f64)
+    }
+
+    /// Same as `resolution`, normalized to meters-at-equator so it can be compared against a
+    /// COG's `Georeference::pixel_resolution_in_meters()` regardless of the grid's own unit.
+    fn resolution_in_meters(&self, zoom: u32) -> f64 {
+        match self.unit {
+            UnitOfMeasure::LinearMeter => self.resolution(zoom),
+            UnitOfMeasure::Degree => lon_to_meters_equator(self.resolution(zoom)),
+        }
+    }
+
+    /// Convert a pixel coordinate - measured from this grid's `origin`, with y growing north, as
+    /// `TMSTileCoords` does - to a point in the grid CRS.
+    fn pixel_to_grid(&self, x: f64, y: f64, zoom: u32) -> (f64, f64) {
+        let res = self.resolution(zoom);
+        (self.origin.0 + x * res, self.origin.1 + y * res)
+    }
+
+    fn tile_pixel_to_grid(&self, tile: &TMSTileCoords, px: f64, py: f64) -> (f64, f64) {
+        self.pixel_to_grid(
+            (tile.x * self.tile_size) as f64 + px,
+            (tile.y * self.tile_size) as f64 + py,
+            tile.z,
+        )
+    }
+
+    /// Bounds, in the grid CRS, of the given tile within this grid.
+    pub fn tile_bounds(&self, tile: &TMSTileCoords) -> BoundingBox {
+        let (xmin, ymin) = self.tile_pixel_to_grid(tile, 0.0, 0.0);
+        let (xmax, ymax) = self.tile_pixel_to_grid(tile, self.tile_size as f64, self.tile_size as f64);
+        BoundingBox {
+            xmin,
+            ymin,
+            xmax,
+            ymax,
+        }
+    }
+}
+
 trait OverviewGeoreferenceCollection {
     fn georeference(&self) -> &Georeference;
     /// Returns the Georeferences for each overview in the COG
@@ -54,8 +148,12 @@ impl OverviewGeoreferenceCollection for COG {
     }
 }
 
-fn find_best_overview(cog: &dyn OverviewGeoreferenceCollection, zoom: u32) -> usize {
-    let tile_res_m = resolution(zoom);
+fn find_best_overview(
+    cog: &dyn OverviewGeoreferenceCollection,
+    tms: &TileMatrixSet,
+    zoom: u32,
+) -> usize {
+    let tile_res_m = tms.resolution_in_meters(zoom);
     let cog_res_m = cog.georeference().geo_transform.x_res;
     println!("tile_res_m={}, cog_res_m={}", tile_res_m, cog_res_m);
 
@@ -74,20 +172,66 @@ fn find_best_overview(cog: &dyn OverviewGeoreferenceCollection, zoom: u32) -> us
     selected_overview_index
 }
 
+#[derive(Clone)]
 pub struct TileData {
     pub img: ImageBuffer,
+    /// Per-pixel validity mask, `TILE_SIZE`x`TILE_SIZE` in row-major order: 255 where the tile
+    /// pixel came from a valid (in-image, non-nodata) source pixel, 0 where it was out-of-image or
+    /// nodata and `img` was left at its zero fill value. Fold this into `img` with
+    /// `ImageBuffer::apply_mask` to get a single RGBA buffer for transparent PNG output.
+    pub mask: Vec<u8>,
     #[allow(dead_code)]
     overview_index: usize,
 }
 
+/// Resampling kernel `extract_tile_with_resampling` uses to turn overview pixels into tile
+/// pixels, matching the choice gdal2tiles/odc-geo expose for a tile load.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resampling {
+    Nearest,
+    Bilinear,
+    Cubic,
+    /// Box-averages the overview pixels covering each output pixel instead of interpolating a
+    /// handful of neighbors, which is what you want when the selected overview is much finer than
+    /// the tile's resolution (e.g. a low zoom level served off the full-resolution overview).
+    Average,
+}
+
+/// Same as `extract_tile`, but always resamples with nearest-neighbor. Most callers don't care
+/// about the resampling kernel, so this is the convenient default - see `extract_tile_with_resampling`
+/// to pick bilinear/cubic/average, a non-WebMercator output grid, or a different edge padding,
+/// instead.
 pub async fn extract_tile(cog: &mut COG, tile_coords: TMSTileCoords) -> Result<TileData, Error> {
-    let overview_index = find_best_overview(cog, tile_coords.z);
-    let overview = &cog.overviews[overview_index];
-    let overview_georef = cog.compute_georeference_for_overview(overview);
+    extract_tile_with_resampling(
+        cog,
+        &TileMatrixSet::web_mercator(),
+        tile_coords,
+        Resampling::Nearest,
+        DEFAULT_TILE_EDGE_PADDING,
+    )
+    .await
+}
+
+/// `edge_padding` is how many overview pixels beyond the tile's footprint to read on each side
+/// before resampling, so `Resampling::Bilinear`/`Cubic` have real neighbor pixels at the tile
+/// boundary instead of clamping to the footprint's edge - which is what produces visible seams
+/// between adjacently-rendered tiles. Pass 0 to read exactly the tile's footprint, as this
+/// function did before `edge_padding` existed.
+pub async fn extract_tile_with_resampling(
+    cog: &mut COG,
+    tms: &TileMatrixSet,
+    tile_coords: TMSTileCoords,
+    resampling: Resampling,
+    edge_padding: u64,
+) -> Result<TileData, Error> {
+    let overview_index = find_best_overview(cog, tms, tile_coords.z);
+    let overview_width = cog.overviews[overview_index].width;
+    let overview_height = cog.overviews[overview_index].height;
+    let overview_georef = cog.compute_georeference_for_overview(&cog.overviews[overview_index]);
 
     // As a first step, read the corresponding area from the overview
     let (overview_area_ul, overview_area_br) = {
-        let warper = Warper::new(&overview_georef)?;
+        let warper = Warper::new(tms, &overview_georef)?;
         let image_bbox = warper.compute_image_pixel_bounding_box(&tile_coords);
         let bbox_ul = vec2f(image_bbox.xmin, image_bbox.ymax);
         let bbox_br = vec2f(image_bbox.xmax, image_bbox.ymin);
@@ -95,33 +239,53 @@ pub async fn extract_tile(cog: &mut COG, tile_coords: TMSTileCoords) -> Result<T
     };
 
     let overview_area_rect = ImageRect {
-        j_from: std::cmp::max(0, overview_area_ul.x as u64),
-        i_from: std::cmp::max(0, overview_area_br.y.ceil() as u64),
-        j_to: std::cmp::min(overview.width, overview_area_br.x.ceil() as u64),
-        i_to: std::cmp::min(overview.height, overview_area_ul.y as u64),
+        j_from: (overview_area_ul.x as u64).saturating_sub(edge_padding),
+        i_from: (overview_area_br.y.ceil() as u64).saturating_sub(edge_padding),
+        j_to: std::cmp::min(overview_width, overview_area_br.x.ceil() as u64 + edge_padding),
+        i_to: std::cmp::min(overview_height, overview_area_ul.y as u64 + edge_padding),
     };
 
-    let nbands = overview.visual_bands_count() as u64;
+    // `make_reader` pulls in the COG's mask overview (if any) alongside the main data overview, so
+    // sparse/nodata areas - whether from a sparse tile in the main overview or from the mask
+    // overview - come through as a zeroed alpha band rather than opaque black.
+    let reader = cog.make_reader(overview_index).await?;
+    let nbands = reader.output_bands() as u64;
+    let has_alpha = reader.has_output_alpha();
     let dtype_size = cog.data_type.size_bytes();
 
+    let color_map = cog.overviews[overview_index].color_map.clone();
+    let is_white_is_zero = cog.overviews[overview_index].photometric_interpretation
+        == PhotometricInterpretation::WhiteIsZero;
+    let is_separated = cog.overviews[overview_index].photometric_interpretation
+        == PhotometricInterpretation::Separated;
+    let reference_black_white = cog.overviews[overview_index].reference_black_white;
+
     // Out of image tile => return transparent
     if overview_area_rect.j_to <= overview_area_rect.j_from
         || overview_area_rect.i_to <= overview_area_rect.i_from
     {
+        let img = ImageBuffer {
+            data: vec![0_u8; (TILE_SIZE * TILE_SIZE * nbands * dtype_size as u64) as usize],
+            width: TILE_SIZE as usize,
+            height: TILE_SIZE as usize,
+            nbands: nbands as usize,
+            has_alpha,
+            data_type: cog.data_type.unpacked_type(),
+            nodata: None,
+        };
         return Ok(TileData {
-            img: ImageBuffer {
-                data: vec![0_u8; (TILE_SIZE * TILE_SIZE * nbands * dtype_size as u64) as usize],
-                width: TILE_SIZE as usize,
-                height: TILE_SIZE as usize,
-                nbands: nbands as usize,
-                data_type: cog.data_type,
+            img: match (&color_map, reference_black_white) {
+                (Some(color_map), _) => img.apply_palette(color_map),
+                (None, _) if is_white_is_zero => img.invert_grayscale(),
+                (None, _) if is_separated => img.cmyk_to_rgb(),
+                (None, Some(rbw)) => img.ycbcr_to_rgb(rbw),
+                (None, None) => img,
             },
+            mask: vec![0_u8; (TILE_SIZE * TILE_SIZE) as usize],
             overview_index,
         });
     }
-    let overview_area_data = overview
-        .make_reader(&mut cog.source)
-        .await?
+    let overview_area_data = reader
         .read_image_part(&mut cog.source, &overview_area_rect)
         .await?;
 
@@ -129,12 +293,28 @@ pub async fn extract_tile(cog: &mut COG, tile_coords: TMSTileCoords) -> Result<T
     // just read
     let mut tile_data: Vec<u8> =
         vec![0; (TILE_SIZE * TILE_SIZE * nbands * dtype_size as u64) as usize];
+    let mut mask: Vec<u8> = vec![0_u8; (TILE_SIZE * TILE_SIZE) as usize];
     {
-        let warper = Warper::new(&overview_georef)?;
+        let warper = Warper::new(tms, &overview_georef)?;
+        // `Resampling::Average` needs to know how many overview pixels map onto one tile pixel,
+        // which `ResampleAlg` doesn't carry on its own - derive it here from how far apart two
+        // adjacent tile pixels land in overview-pixel space.
+        let resample = match resampling {
+            Resampling::Nearest => ResampleAlg::Nearest,
+            Resampling::Bilinear => ResampleAlg::Bilinear,
+            Resampling::Cubic => ResampleAlg::Cubic,
+            Resampling::Average => {
+                let origin = warper.project_tile_pixel(&tile_coords, 0.0, 0.0);
+                let next_x = warper.project_tile_pixel(&tile_coords, 1.0, 0.0);
+                let next_y = warper.project_tile_pixel(&tile_coords, 0.0, 1.0);
+                ResampleAlg::Average {
+                    radius_x: (next_x.x - origin.x).abs() / 2.0,
+                    radius_y: (next_y.y - origin.y).abs() / 2.0,
+                }
+            }
+        };
         for i in 0..TILE_SIZE {
-            // TODO: Given we assert PlanarConfiguration, can use some memcpy below
             for j in 0..TILE_SIZE {
-                // TODO: Naive nearest neighbor => replace by bilinear (or make this selectable)
                 // Compute the 3857/projeced position of that pixel
                 let overview_pixel = warper.project_tile_pixel(&tile_coords, j as f64, i as f64);
 
@@ -152,11 +332,12 @@ pub async fn extract_tile(cog: &mut COG, tile_coords: TMSTileCoords) -> Result<T
                 {
                     continue;
                 }
-                // We clamp again just out of caution to avoid out of bounds due to rounding errors or something
-                let overview_area_x = (overview_pixel.x as i64 - overview_area_rect.j_from as i64)
-                    .clamp(0, overview_area_rect.width() as i64 - 1);
-                let overview_area_y = (overview_pixel.y as i64 - overview_area_rect.i_from as i64)
-                    .clamp(0, overview_area_rect.height() as i64 - 1);
+                // Position within overview_area_data, clamped just out of caution to avoid out of
+                // bounds reads due to rounding errors or the margin above
+                let overview_area_x = (overview_pixel.x - overview_area_rect.j_from as f64)
+                    .clamp(0.0, overview_area_rect.width() as f64 - 1.0);
+                let overview_area_y = (overview_pixel.y - overview_area_rect.i_from as f64)
+                    .clamp(0.0, overview_area_rect.height() as f64 - 1.0);
 
                 // We need to flip i here because i, j are in TMS coordinates with i/y growing north
                 // but in raster space, y is growing south
@@ -164,39 +345,54 @@ pub async fn extract_tile(cog: &mut COG, tile_coords: TMSTileCoords) -> Result<T
 
                 let tile_data_start_offset =
                     ((i * TILE_SIZE * nbands + j * nbands) * dtype_size as u64) as usize;
-                let overview_data_start_offset =
-                    ((overview_area_y as u64 * overview_area_rect.width() * nbands
-                        + overview_area_x as u64 * nbands)
-                        * dtype_size as u64) as usize;
-                let nbytes = nbands as usize * dtype_size;
-                tile_data[tile_data_start_offset..tile_data_start_offset + nbytes].copy_from_slice(
-                    &overview_area_data.data
-                        [overview_data_start_offset..overview_data_start_offset + nbytes],
+                overview_area_data.sample_at(
+                    overview_area_x,
+                    overview_area_y,
+                    resample,
+                    &mut tile_data,
+                    tile_data_start_offset,
                 );
+                mask[(i * TILE_SIZE + j) as usize] =
+                    if overview_area_data.is_valid_at(overview_area_x, overview_area_y) {
+                        255
+                    } else {
+                        0
+                    };
             }
         }
     }
 
+    let img = ImageBuffer {
+        data: tile_data,
+        width: TILE_SIZE as usize,
+        height: TILE_SIZE as usize,
+        nbands: nbands as usize,
+        has_alpha,
+        data_type: cog.data_type.unpacked_type(),
+        nodata: None,
+    };
     Ok(TileData {
-        img: ImageBuffer {
-            data: tile_data,
-            width: TILE_SIZE as usize,
-            height: TILE_SIZE as usize,
-            nbands: nbands as usize,
-            data_type: cog.data_type,
+        img: match (&color_map, reference_black_white) {
+            // A paletted overview's samples are indices, not visualizable values in their own
+            // right, so expand them to RGB here instead of leaving that to the caller.
+            (Some(color_map), _) => img.apply_palette(color_map),
+            // A WhiteIsZero overview's samples are inverted relative to the far more common
+            // BlackIsZero, so flip them here rather than leaving that to the caller.
+            (None, _) if is_white_is_zero => img.invert_grayscale(),
+            // A Separated (CMYK) overview's bands aren't RGB on their own, so convert here too.
+            (None, _) if is_separated => img.cmyk_to_rgb(),
+            // A non-JPEG YCbCr overview's bands aren't RGB on their own either.
+            (None, Some(rbw)) => img.ycbcr_to_rgb(rbw),
+            (None, None) => img,
         },
+        mask,
         overview_index,
     })
 }
 
 /// Returns pixel size at a given zoom level of pyramid of EPSG:3857
 fn resolution(zoom: u32) -> f64 {
-    // Important, 256 is NOT TILE_SIZE, it is the number of pixels that are
-    // covered at zoom level 0
-    // See Leaflet's scale function:
-    // https://github.com/Leaflet/Leaflet/blob/37d2fd15ad6518c254fae3e033177e96c48b5012/src/geo/crs/CRS.js#L62
-    let initial_resolution = 2.0 * std::f64::consts::PI * EARTH_RADIUS_METERS / 256.0;
-    initial_resolution / (2.0_f64.powf(zoom as f64))
+    TileMatrixSet::web_mercator().resolution(zoom)
 }
 
 /// Convert pixel coordinates in given zoom level of pyramid to EPSG:3857
@@ -205,10 +401,7 @@ pub fn pixel_to_meters(x: f64, y: f64, zoom: u32) -> (f64, f64) {
     // The 3857 coordinate system has x grow left and y upwards
     // The XYZ tile coordinates have x grow left and y downwards
     //
-    let res = resolution(zoom);
-    let mx = x * res + TOP_LEFT_METERS.0;
-    let my = y * res + TOP_LEFT_METERS.1;
-    (mx, my)
+    TileMatrixSet::web_mercator().pixel_to_grid(x, y, zoom)
 }
 
 impl TMSTileCoords {
@@ -220,26 +413,6 @@ impl TMSTileCoords {
             z,
         }
     }
-
-    /// Convert from pixel coordinates within this tile to 3857 meters
-    fn tile_pixel_to_3857_meters(&self, px: f64, py: f64) -> (f64, f64) {
-        pixel_to_meters(
-            (self.x * TILE_SIZE) as f64 + px,
-            (self.y * TILE_SIZE) as f64 + py,
-            self.z,
-        )
-    }
-
-    fn tile_bounds_3857(&self) -> BoundingBox {
-        let (xmin, ymin) = self.tile_pixel_to_3857_meters(0.0, 0.0);
-        let (xmax, ymax) = self.tile_pixel_to_3857_meters(TILE_SIZE as f64, TILE_SIZE as f64);
-        BoundingBox {
-            xmin,
-            ymin,
-            xmax,
-            ymax,
-        }
-    }
 }
 
 #[cfg(test)]
@@ -275,8 +448,9 @@ mod tests {
         // https://www.maptiler.com/google-maps-coordinates-tile-bounds-projection/
         // https://epsg.io/map#srs=3857&x=-20037508.34&y=20048966.1&z=2&layer=streets
         // (click on a tile and look at 'spherical mercator (meters) bounds')
+        let tms = TileMatrixSet::web_mercator();
         assert_bbox_equal(
-            &TMSTileCoords { x: 0, y: 0, z: 0 }.tile_bounds_3857(),
+            &tms.tile_bounds(&TMSTileCoords { x: 0, y: 0, z: 0 }),
             &BoundingBox {
                 xmin: -20037508.342789244,
                 ymin: -20037508.342789244,
@@ -286,7 +460,7 @@ mod tests {
             1e-5,
         );
         assert_bbox_equal(
-            &TMSTileCoords { x: 0, y: 1, z: 1 }.tile_bounds_3857(),
+            &tms.tile_bounds(&TMSTileCoords { x: 0, y: 1, z: 1 }),
             &BoundingBox {
                 xmin: -20037508.342789244,
                 ymin: 0.0,
@@ -296,7 +470,7 @@ mod tests {
             1e-5,
         );
         assert_bbox_equal(
-            &TMSTileCoords { x: 1, y: 1, z: 1 }.tile_bounds_3857(),
+            &tms.tile_bounds(&TMSTileCoords { x: 1, y: 1, z: 1 }),
             &BoundingBox {
                 xmin: 0.0,
                 ymin: 0.0,
@@ -306,7 +480,7 @@ mod tests {
             1e-5,
         );
         assert_bbox_equal(
-            &TMSTileCoords { x: 17, y: 18, z: 5 }.tile_bounds_3857(),
+            &tms.tile_bounds(&TMSTileCoords { x: 17, y: 18, z: 5 }),
             &BoundingBox {
                 xmin: 1252344.0,
                 ymin: 2504689.0,
@@ -317,6 +491,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tile_bounds_world_crs84_quad() {
+        // WorldCRS84Quad has two zoom-0 root tiles side by side, together covering the whole
+        // -180..180 / -90..90 extent
+        let tms = TileMatrixSet::world_crs84_quad();
+        assert_bbox_equal(
+            &tms.tile_bounds(&TMSTileCoords { x: 0, y: 0, z: 0 }),
+            &BoundingBox {
+                xmin: -180.0,
+                ymin: -90.0,
+                xmax: 0.0,
+                ymax: 90.0,
+            },
+            1e-9,
+        );
+        assert_bbox_equal(
+            &tms.tile_bounds(&TMSTileCoords { x: 1, y: 0, z: 0 }),
+            &BoundingBox {
+                xmin: 0.0,
+                ymin: -90.0,
+                xmax: 180.0,
+                ymax: 90.0,
+            },
+            1e-9,
+        );
+    }
+
     struct FakeCOG {
         georef: Georeference,
         overviews_georef: Vec<Georeference>,
@@ -341,6 +542,8 @@ mod tests {
                 ul_y: 0.0,
                 x_res: res_m,
                 y_res: res_m,
+                x_rotation: 0.0,
+                y_rotation: 0.0,
             },
         }
     }
@@ -357,7 +560,7 @@ mod tests {
         };
         // Zoom level to size reference
         // https://wiki.openstreetmap.org/wiki/Zoom_levels
-        assert_eq!(find_best_overview(&cog, 15), 1);
+        assert_eq!(find_best_overview(&cog, &TileMatrixSet::web_mercator(), 15), 1);
     }
 
     fn make_degrees_georeference(res_m_equator: f64) -> Georeference {
@@ -370,6 +573,8 @@ mod tests {
                 ul_y: 0.0,
                 x_res: res_deg,
                 y_res: res_deg,
+                x_rotation: 0.0,
+                y_rotation: 0.0,
             },
         }
     }
@@ -386,7 +591,7 @@ mod tests {
         };
         // Zoom level to size reference
         // https://wiki.openstreetmap.org/wiki/Zoom_levels
-        assert_eq!(find_best_overview(&cog, 15), 1);
+        assert_eq!(find_best_overview(&cog, &TileMatrixSet::web_mercator(), 15), 1);
     }
 
     #[tokio::test]
@@ -414,6 +619,8 @@ mod tests {
         assert_eq!(expected.width, 256);
         assert_eq!(expected.height, 256);
         assert_eq!(tile_data.img.data, expected.data);
+        // Fully covered by the image, so every pixel should be valid
+        assert_eq!(tile_data.mask, vec![255_u8; 256 * 256]);
     }
 
     #[tokio::test]
@@ -572,6 +779,7 @@ mod tests {
         assert_eq!(tile_data.img.width, 256);
         assert_eq!(tile_data.img.height, 256);
         assert_eq!(tile_data.img.data, vec![0_u8; 256 * 256 * 3]);
+        assert_eq!(tile_data.mask, vec![0_u8; 256 * 256]);
     }
 
     #[tokio::test]
@@ -5,53 +5,51 @@ use proj::Transform;
 use crate::tiff::georef::Georeference;
 use crate::Error;
 
-use super::{BoundingBox, TMSTileCoords, Vec2f};
+use super::{BoundingBox, TMSTileCoords, TileMatrixSet, Vec2f};
 
-/// This Warps from TMS (so 3857) to a raster in the given Crs/Georeference
+/// Warps from the given output tile grid (`TileMatrixSet`) to a raster in the given Crs/Georeference
 pub struct Warper<'a> {
     transform: Transform,
     georef: &'a Georeference,
+    tms: &'a TileMatrixSet,
 }
 
-impl Warper<'_> {
-    pub fn new(georef: &Georeference) -> Result<Warper, Error> {
+impl<'a> Warper<'a> {
+    pub fn new(tms: &'a TileMatrixSet, georef: &'a Georeference) -> Result<Warper<'a>, Error> {
         Ok(Warper {
-            transform: Transform::new(3857, georef.crs.epsg_code())?,
+            transform: Transform::new(&tms.crs_proj_spec, &georef.crs.proj_spec()?)?,
             georef,
+            tms,
         })
     }
 
     /// Project a pixel at (px, py) in the tile to image coordinates
-    /// px, py should be in [0, 256] since we use a 256 tile width
+    /// px, py should be in [0, tile_size] since we use a 256 tile width
     pub fn project_tile_pixel(&self, tile: &TMSTileCoords, px: f64, py: f64) -> Vec2f {
-        let (x_3857, y_3857) = tile.tile_pixel_to_3857_meters(px, py);
-        self.project_3857_meters(x_3857, y_3857)
+        let (x_grid, y_grid) = self.tms.tile_pixel_to_grid(tile, px, py);
+        self.project_grid_point(x_grid, y_grid)
     }
 
-    /// Project a point in 3857 meters coordinate to image coordinates
-    pub fn project_3857_meters(&self, x_3857: f64, y_3857: f64) -> Vec2f {
-        let (x_proj, y_proj) = self.transform.transform((x_3857, y_3857));
+    /// Project a point in the output grid's CRS to image coordinates
+    pub fn project_grid_point(&self, x_grid: f64, y_grid: f64) -> Vec2f {
+        let (x_proj, y_proj) = self.transform.transform((x_grid, y_grid));
         // Reverse the geotransform, see https://gdal.org/tutorials/geotransforms_tut.html
-        // x_proj = ul_x + overview_pixel_x * x_res;
-        // y_proj = ul_y + overview_pixel_y * y_res;
-        //
-        // Here we reverse that to find overview_pixel_ from x/y_proj
-        // => (x_proj - ul_x) / x_res = overview_pixel_x
-        let overview_pixel_x =
-            (x_proj - self.georef.geo_transform.ul_x) / self.georef.geo_transform.x_res;
-
-        let overview_pixel_y =
-            (y_proj - self.georef.geo_transform.ul_y) / self.georef.geo_transform.y_res;
-        Vec2f {
-            x: overview_pixel_x,
-            y: overview_pixel_y,
-        }
+        // Rotated/sheared rasters (non-zero x_rotation/y_rotation) are rejected at `COG::open`
+        // time (see `Georeference::decode`), so this is always a plain axis-aligned scale+offset
+        // to invert (never singular).
+        self.georef
+            .geo_transform
+            .world_to_pixel(Vec2f {
+                x: x_proj,
+                y: y_proj,
+            })
+            .expect("Warper's geo_transform is always non-singular (axis-aligned scale+offset)")
     }
 
     // For a given TMS tile, computes the bounding box on the source image specified by
     // `source_crs` and `source_georef`
     pub fn compute_image_pixel_bounding_box(&self, tile_coords: &TMSTileCoords) -> BoundingBox {
-        let tile_bounds = tile_coords.tile_bounds_3857();
+        let tile_bounds = self.tms.tile_bounds(tile_coords);
         let edges = tile_bounds.edges();
         // We use a similar algorithm as GDAL and project 21 points against each edge of the tile
         // onto the destination and compute the bbox from that
@@ -68,7 +66,7 @@ impl Warper<'_> {
         // project points to image
         let image_points: Vec<Vec2f> = points
             .iter()
-            .map(|p| self.project_3857_meters(p.x, p.y))
+            .map(|p| self.project_grid_point(p.x, p.y))
             .collect();
         BoundingBox::from_points(&image_points)
     }
@@ -98,9 +96,11 @@ mod tests {
                 ul_y: TOP_LEFT_METERS.1,
                 x_res: EARTH_EQUATOR_CIRCUMFERENCE / image_size,
                 y_res: EARTH_EQUATOR_CIRCUMFERENCE / image_size,
+                x_rotation: 0.0,
+                y_rotation: 0.0,
             },
         };
-        let warper = Warper::new(&georef).unwrap();
+        let warper = Warper::new(&TileMatrixSet::web_mercator(), &georef).unwrap();
         // Getting the (0, 0, 0) tile should just cover the whole image in one tile
         let bbox = warper.compute_image_pixel_bounding_box(&TMSTileCoords::from_zxy(0, 0, 0));
         assert_float_eq(bbox.xmin, 0.0, 1e-5);